@@ -0,0 +1,307 @@
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf};
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+use super::profile::{config_path, write_config};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Shoot,
+    Scoreboard,
+    Chat,
+    Debug,
+    Quit,
+    Bindings,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Shoot,
+        Action::Scoreboard,
+        Action::Chat,
+        Action::Debug,
+        Action::Quit,
+        Action::Bindings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Shoot => "Shoot",
+            Action::Scoreboard => "Scoreboard",
+            Action::Chat => "Chat",
+            Action::Debug => "Debug overlay",
+            Action::Quit => "Quit",
+            Action::Bindings => "Key bindings",
+        }
+    }
+}
+
+/// A key or mouse button, stored in the file by name: `"W"`, `"Space"`,
+/// `"MouseLeft"`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+const KEYS: [KeyCode; 72] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::Escape,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::LeftShift,
+    KeyCode::LeftControl,
+    KeyCode::LeftAlt,
+    KeyCode::RightShift,
+    KeyCode::RightControl,
+    KeyCode::RightAlt,
+];
+
+const MOUSE_BUTTONS: [(MouseButton, &str); 3] = [
+    (MouseButton::Left, "MouseLeft"),
+    (MouseButton::Right, "MouseRight"),
+    (MouseButton::Middle, "MouseMiddle"),
+];
+
+impl Binding {
+    pub fn is_down(self) -> bool {
+        match self {
+            Binding::Key(key) => is_key_down(key),
+            Binding::Mouse(button) => is_mouse_button_down(button),
+        }
+    }
+
+    /// The key or mouse button pressed this frame, if it can be bound.
+    pub fn pressed() -> Option<Binding> {
+        if let Some(key) = get_last_key_pressed()
+            && KEYS.contains(&key)
+        {
+            return Some(Binding::Key(key));
+        }
+        MOUSE_BUTTONS
+            .iter()
+            .find(|(button, _)| is_mouse_button_pressed(*button))
+            .map(|(button, _)| Binding::Mouse(*button))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(button) => {
+                let name = MOUSE_BUTTONS
+                    .iter()
+                    .find(|(b, _)| b == button)
+                    .map_or("MouseUnknown", |(_, name)| name);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        binding.to_string()
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        if let Some((button, _)) = MOUSE_BUTTONS
+            .iter()
+            .find(|(_, n)| n.eq_ignore_ascii_case(&name))
+        {
+            return Ok(Binding::Mouse(*button));
+        }
+        KEYS.iter()
+            .find(|key| format!("{:?}", key).eq_ignore_ascii_case(&name))
+            .map(|key| Binding::Key(*key))
+            .ok_or_else(|| format!("unknown key `{}`", name))
+    }
+}
+
+/// Action to keys map, read from `bindings.toml` in the user config dir.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(transparent)]
+pub struct Bindings {
+    actions: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = Binding::Key;
+        let actions = BTreeMap::from([
+            (
+                Action::MoveUp,
+                vec![key(KeyCode::W), key(KeyCode::Up), key(KeyCode::K)],
+            ),
+            (
+                Action::MoveDown,
+                vec![key(KeyCode::S), key(KeyCode::Down), key(KeyCode::J)],
+            ),
+            (
+                Action::MoveLeft,
+                vec![key(KeyCode::A), key(KeyCode::Left), key(KeyCode::H)],
+            ),
+            (
+                Action::MoveRight,
+                vec![key(KeyCode::D), key(KeyCode::Right), key(KeyCode::L)],
+            ),
+            (Action::Shoot, vec![key(KeyCode::Space)]),
+            (Action::Scoreboard, vec![key(KeyCode::Tab)]),
+            (Action::Chat, vec![key(KeyCode::Enter), key(KeyCode::T)]),
+            (Action::Debug, vec![key(KeyCode::F3)]),
+            (Action::Quit, vec![key(KeyCode::Q)]),
+            (Action::Bindings, vec![key(KeyCode::F1)]),
+        ]);
+        Self { actions }
+    }
+}
+
+impl Bindings {
+    pub fn path() -> Option<PathBuf> {
+        config_path(config::BINDINGS_FILE)
+    }
+
+    /// Reads the bindings file, writing the defaults on first run. A broken
+    /// file is reported and left untouched; actions it lacks use defaults.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                let bindings = Self::default();
+                bindings.save();
+                return bindings;
+            }
+        };
+
+        match toml::from_str::<Bindings>(&text) {
+            Ok(mut bindings) => {
+                for (action, keys) in Self::default().actions {
+                    bindings.actions.entry(action).or_insert(keys);
+                }
+                bindings
+            }
+            Err(e) => {
+                eprintln!("Invalid key bindings in {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> bool {
+        match toml::to_string(self) {
+            Ok(text) => write_config(config::BINDINGS_FILE, &text),
+            Err(e) => {
+                eprintln!("Failed to serialize key bindings: {}", e);
+                false
+            }
+        }
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn set(&mut self, action: Action, keys: Vec<Binding>) {
+        self.actions.insert(action, keys);
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.get(action).iter().any(|binding| binding.is_down())
+    }
+
+    /// True only on the frame the action's key or button went down.
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.get(action).iter().any(|binding| match binding {
+            Binding::Key(key) => is_key_pressed(*key),
+            Binding::Mouse(button) => is_mouse_button_pressed(*button),
+        })
+    }
+}
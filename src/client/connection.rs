@@ -0,0 +1,447 @@
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config,
+    game::state::{Direction, GameState},
+    map::Map,
+    network::{
+        packet::{PacketReader, PacketWriter},
+        state::{Capabilities, ClientMessage, MapDownloader, ServerMessage, ServerMessageType},
+    },
+    utils,
+};
+
+use super::{state::ClientState, tcp};
+
+/// A connection to a game server: owns the UDP socket and the threads that
+/// join, download the map, receive state and send input. Front-ends only
+/// send input, call `poll` once per frame and read `state()`.
+pub struct Connection {
+    server_addr: SocketAddr,
+    tcp_addr: SocketAddr,
+    name: String,
+    tx: Sender<ClientMessage>,
+    state: Arc<Mutex<ClientState>>,
+    map_downloader: Arc<Mutex<MapDownloader>>,
+    map_loaded: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+    sender: Option<JoinHandle<()>>,
+    viewport: Option<(f32, f32)>,
+    reported_viewport: Option<(f32, f32)>,
+    last_net_stats: Instant,
+    /// When loading last made progress, and how many map chunks it had.
+    loading_progress: (Instant, usize),
+}
+
+impl Connection {
+    /// Joins over TCP when possible and falls back to a UDP `Init` otherwise.
+    /// The server may adjust `name` to make it valid and unique.
+    pub fn connect(server_addr: SocketAddr, tcp_addr: SocketAddr, name: &str) -> io::Result<Self> {
+        let joined = tcp::join(tcp_addr, name).map(|(player, token)| (player.id, token));
+        Self::open(server_addr, tcp_addr, name, joined)
+    }
+
+    /// Takes over an already joined player from a new socket by binding with
+    /// its join token, e.g. after the old address stopped getting through.
+    pub fn reattach(
+        server_addr: SocketAddr,
+        tcp_addr: SocketAddr,
+        name: &str,
+        player_id: u32,
+        token: u64,
+    ) -> io::Result<Self> {
+        Self::open(server_addr, tcp_addr, name, Some((player_id, token)))
+    }
+
+    /// Starts the network threads. With a player id and join token the socket
+    /// binds to that player, otherwise it joins with a UDP `Init`.
+    fn open(
+        server_addr: SocketAddr,
+        tcp_addr: SocketAddr,
+        name: &str,
+        joined: Option<(u32, u64)>,
+    ) -> io::Result<Self> {
+        let socket = utils::bind_udp_for(server_addr)?;
+        socket.set_nonblocking(false)?;
+        let recv_socket = socket.try_clone()?;
+        recv_socket.set_read_timeout(Some(config::CLIENT_POLL_INTERVAL))?;
+
+        let (tx, rx) = mpsc::channel::<ClientMessage>();
+        let state = Arc::new(Mutex::new(ClientState::new()));
+        let map_downloader = Arc::new(Mutex::new(MapDownloader::new()));
+        let map_loaded = Arc::new(AtomicBool::new(false));
+        let running = Arc::new(AtomicBool::new(true));
+        let compression = Arc::new(AtomicBool::new(false));
+
+        match joined {
+            Some((player_id, token)) => {
+                {
+                    let mut state = state.lock().unwrap();
+                    state.id = Some(player_id);
+                    state.join_token = Some(token);
+                }
+
+                let tx_bind = tx.clone();
+                let state_bind = Arc::clone(&state);
+                let running_bind = Arc::clone(&running);
+                thread::spawn(move || {
+                    while running_bind.load(Ordering::Relaxed)
+                        && state_bind.lock().unwrap().game_state.is_none()
+                    {
+                        let _ = tx_bind.send(ClientMessage::Hello(Capabilities::supported()));
+                        let _ = tx_bind.send(ClientMessage::Bind(token));
+                        thread::sleep(Duration::from_secs(1));
+                    }
+                });
+            }
+            None => {
+                let _ = tx.send(ClientMessage::Hello(Capabilities::supported()));
+                let _ = tx.send(ClientMessage::Init(name.to_string()));
+            }
+        }
+
+        let tx_map = tx.clone();
+        let map_downloader_map = Arc::clone(&map_downloader);
+        let map_loaded_map = Arc::clone(&map_loaded);
+        let running_map = Arc::clone(&running);
+        thread::spawn(move || {
+            run_map_requests(
+                tcp_addr,
+                tx_map,
+                map_downloader_map,
+                map_loaded_map,
+                running_map,
+            );
+        });
+
+        let tx_recv = tx.clone();
+        let state_recv = Arc::clone(&state);
+        let map_downloader_recv = Arc::clone(&map_downloader);
+        let map_loaded_recv = Arc::clone(&map_loaded);
+        let compression_recv = Arc::clone(&compression);
+        let running_recv = Arc::clone(&running);
+        thread::spawn(move || {
+            let mut reader = PacketReader::<ServerMessage>::new();
+            while running_recv.load(Ordering::Relaxed) {
+                let Some((msg, _, size)) = reader.recv_sized(&recv_socket) else {
+                    continue;
+                };
+                handle_message(
+                    msg.message,
+                    size,
+                    &tx_recv,
+                    &state_recv,
+                    &map_downloader_recv,
+                    &map_loaded_recv,
+                    &compression_recv,
+                );
+            }
+        });
+
+        let running_send = Arc::clone(&running);
+        let sender = thread::spawn(move || {
+            run_sender(socket, server_addr, rx, compression, running_send);
+        });
+
+        Ok(Self {
+            server_addr,
+            tcp_addr,
+            name: name.to_string(),
+            tx,
+            state,
+            map_downloader,
+            map_loaded,
+            running,
+            sender: Some(sender),
+            viewport: None,
+            reported_viewport: None,
+            last_net_stats: Instant::now(),
+            loading_progress: (Instant::now(), 0),
+        })
+    }
+
+    pub fn server_addr(&self) -> SocketAddr {
+        self.server_addr
+    }
+
+    pub fn tcp_addr(&self) -> SocketAddr {
+        self.tcp_addr
+    }
+
+    /// Nickname asked for when connecting.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Relevance half extent the server uses for this client, in tiles.
+    pub fn relevance_viewport(&self) -> (f32, f32) {
+        self.reported_viewport
+            .and_then(|(half_w, half_h)| GameState::clamp_viewport(half_w, half_h))
+            .unwrap_or((
+                config::DEFAULT_VIEW_HALF_EXTENT,
+                config::DEFAULT_VIEW_HALF_EXTENT,
+            ))
+    }
+
+    /// Nothing has arrived from the server for `CONNECTION_INTERRUPTED_AFTER`.
+    pub fn is_interrupted(&self) -> bool {
+        self.state().silence() >= config::CONNECTION_INTERRUPTED_AFTER
+    }
+
+    /// Why joining failed, once neither the game state nor the whole map has
+    /// arrived and no new map chunk came in for `JOIN_TIMEOUT`.
+    pub fn loading_failure(&self) -> Option<String> {
+        let gs_ready = self.state().game_state.is_some();
+        if (gs_ready && self.map_loaded())
+            || self.loading_progress.0.elapsed() <= config::JOIN_TIMEOUT
+        {
+            return None;
+        }
+
+        let (received, total) = self.map_progress();
+        Some(if !gs_ready {
+            "No game state from the server".to_string()
+        } else if total == 0 {
+            "No map data from the server".to_string()
+        } else {
+            format!("Map download stalled at {}/{} chunks", received, total)
+        })
+    }
+
+    pub fn send(&self, msg: ClientMessage) {
+        let _ = self.tx.send(msg);
+    }
+
+    pub fn send_move(&self, direction: Direction) {
+        self.send(ClientMessage::Move(direction));
+    }
+
+    pub fn shoot(&self) {
+        self.send(ClientMessage::Shoot);
+    }
+
+    pub fn send_chat(&self, text: &str) {
+        self.send(ClientMessage::Chat(text.to_string()));
+    }
+
+    /// Visible half extent in tiles; reported to the server by `poll`.
+    pub fn set_viewport(&mut self, half_w: f32, half_h: f32) {
+        self.viewport = Some((half_w, half_h));
+    }
+
+    /// Periodic duties: network stats, viewport reports and resync requests.
+    pub fn poll(&mut self) {
+        let (received, _) = self.map_progress();
+        if received > self.loading_progress.1 {
+            self.loading_progress = (Instant::now(), received);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let stats_due = self.last_net_stats.elapsed() >= config::NET_STATS_INTERVAL;
+
+        // Re-sent with every stats report in case the first one was lost.
+        if state.game_state.is_some()
+            && let Some((half_w, half_h)) = self.viewport
+            && (stats_due || self.reported_viewport != self.viewport)
+        {
+            let _ = self.tx.send(ClientMessage::Viewport { half_w, half_h });
+            self.reported_viewport = self.viewport;
+        }
+        if state.take_resync_request() {
+            let _ = self.tx.send(ClientMessage::Resync);
+        }
+        if stats_due {
+            let (last_seq, received) = state.take_net_stats();
+            let _ = self.tx.send(ClientMessage::NetStats { last_seq, received });
+            self.last_net_stats = Instant::now();
+        }
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, ClientState> {
+        self.state.lock().unwrap()
+    }
+
+    pub fn map_downloader(&self) -> &Mutex<MapDownloader> {
+        &self.map_downloader
+    }
+
+    /// Received and total map chunks.
+    pub fn map_progress(&self) -> (usize, usize) {
+        self.map_downloader.lock().unwrap().progress()
+    }
+
+    pub fn map_loaded(&self) -> bool {
+        self.map_loaded.load(Ordering::Relaxed)
+    }
+
+    /// Builds the map once every chunk has arrived.
+    pub fn build_map(&self) -> Option<Map> {
+        if !self.map_loaded() {
+            return None;
+        }
+        self.map_downloader.lock().unwrap().try_build_map()
+    }
+
+    /// Player id and join token once joined, for `reattach`.
+    pub fn join_token(&self) -> Option<(u32, u64)> {
+        let state = self.state();
+        state.id.zip(state.join_token)
+    }
+
+    /// Sends `Quit` and waits for it to leave the socket.
+    pub fn disconnect(mut self) {
+        self.send(ClientMessage::Quit);
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.join();
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn handle_message(
+    message: ServerMessageType,
+    size: usize,
+    tx: &Sender<ClientMessage>,
+    state: &Mutex<ClientState>,
+    map_downloader: &Mutex<MapDownloader>,
+    map_loaded: &AtomicBool,
+    compression: &AtomicBool,
+) {
+    let mut state = state.lock().unwrap();
+    state.last_packet = Instant::now();
+    state.record_received(size, matches!(message, ServerMessageType::GameStateDiff(_)));
+    match message {
+        ServerMessageType::InitPlayer { player, token } => {
+            state.init_player(*player);
+            state.join_token.get_or_insert(token);
+        }
+        ServerMessageType::Map(chunk) => {
+            let mut map_downloader = map_downloader.lock().unwrap();
+            map_loaded.store(map_downloader.load_chunk(chunk), Ordering::Relaxed);
+        }
+        ServerMessageType::GameState(game_state) => {
+            state.update_state(*game_state);
+        }
+        ServerMessageType::GameStateDiff(state_diff) => {
+            state.update_state_diff(state_diff);
+        }
+        ServerMessageType::Hello(agreed) => {
+            compression.store(agreed.compression, Ordering::Relaxed);
+        }
+        ServerMessageType::Shutdown(reason) => {
+            state.server_closed = Some(reason);
+        }
+        ServerMessageType::Ping(nonce) => {
+            let _ = tx.send(ClientMessage::Pong(nonce));
+        }
+        ServerMessageType::Scoreboard { total, entries } => {
+            state.update_scoreboard(total, entries);
+        }
+        ServerMessageType::Events { first_seq, events } => {
+            let next_expected = state.receive_events(first_seq, events);
+            let _ = tx.send(ClientMessage::EventAck(next_expected));
+        }
+        ServerMessageType::Chat {
+            first_seq,
+            messages,
+        } => {
+            let next_expected = state.receive_chat(first_seq, messages);
+            let _ = tx.send(ClientMessage::ChatAck(next_expected));
+        }
+        ServerMessageType::Welcome { .. }
+        | ServerMessageType::Admin(_)
+        | ServerMessageType::Info(_) => {}
+    }
+}
+
+/// Asks for missing chunks over UDP and switches to TCP when that stops making progress.
+fn run_map_requests(
+    tcp_addr: SocketAddr,
+    tx: Sender<ClientMessage>,
+    map_downloader: Arc<Mutex<MapDownloader>>,
+    map_loaded: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+) {
+    let mut last_progress = Instant::now();
+    let mut last_received = 0;
+    while running.load(Ordering::Relaxed) && !map_loaded.load(Ordering::Relaxed) {
+        let (received, _) = map_downloader.lock().unwrap().progress();
+        if received > last_received {
+            last_received = received;
+            last_progress = Instant::now();
+        }
+
+        if last_progress.elapsed() >= tcp_fallback_delay() {
+            if tcp::download_map(tcp_addr, &map_downloader) {
+                map_loaded.store(true, Ordering::Relaxed);
+                break;
+            }
+            last_progress = Instant::now();
+        }
+
+        let map_chunk_ids = { map_downloader.lock().unwrap().get_exist_chunk_id() };
+
+        let _ = tx.send(ClientMessage::Map(map_chunk_ids));
+        thread::sleep(config::MAP_REQUEST_INTERVAL);
+    }
+}
+
+/// How long UDP map requests may go without a new chunk before the TCP
+/// download starts. Must leave most of `JOIN_TIMEOUT` for that download.
+fn tcp_fallback_delay() -> Duration {
+    config::MAP_REQUEST_INTERVAL * config::MAP_UDP_MAX_FAILED_ATTEMPTS
+}
+
+fn run_sender(
+    socket: UdpSocket,
+    server_addr: SocketAddr,
+    rx: Receiver<ClientMessage>,
+    compression: Arc<AtomicBool>,
+    running: Arc<AtomicBool>,
+) {
+    let mut writer = PacketWriter::new(server_addr);
+    loop {
+        let msg = match rx.recv_timeout(config::CLIENT_POLL_INTERVAL) {
+            Ok(msg) => msg,
+            Err(RecvTimeoutError::Timeout) if running.load(Ordering::Relaxed) => continue,
+            Err(_) => return,
+        };
+
+        writer.set_compression(compression.load(Ordering::Relaxed));
+        writer.push(&socket, &msg);
+        for msg in rx.try_iter() {
+            writer.push(&socket, &msg);
+        }
+        writer.flush(&socket);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tcp_fallback_starts_well_inside_join_timeout() {
+        assert!(tcp_fallback_delay() * 2 < config::JOIN_TIMEOUT);
+    }
+}
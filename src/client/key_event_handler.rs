@@ -1,30 +1,41 @@
-use macroquad::prelude::*;
-
 use crate::game::state::Direction;
 
-pub fn listen_move() -> Option<Direction> {
-    let up = is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) || is_key_down(KeyCode::K);
-    let down = is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) || is_key_down(KeyCode::J);
-    let left = is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) || is_key_down(KeyCode::H);
-    let right = is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) || is_key_down(KeyCode::L);
+use super::bindings::{Action, Bindings};
 
-    if up {
+pub fn listen_move(bindings: &Bindings) -> Option<Direction> {
+    if bindings.is_down(Action::MoveUp) {
         Some(Direction::Up)
-    } else if down {
+    } else if bindings.is_down(Action::MoveDown) {
         Some(Direction::Down)
-    } else if left {
+    } else if bindings.is_down(Action::MoveLeft) {
         Some(Direction::Left)
-    } else if right {
+    } else if bindings.is_down(Action::MoveRight) {
         Some(Direction::Right)
     } else {
         None
     }
 }
 
-pub fn listen_shoot() -> bool {
-    is_key_down(KeyCode::Space)
+pub fn listen_shoot(bindings: &Bindings) -> bool {
+    bindings.is_down(Action::Shoot)
+}
+
+pub fn listen_scoreboard(bindings: &Bindings) -> bool {
+    bindings.is_down(Action::Scoreboard)
+}
+
+pub fn listen_chat(bindings: &Bindings) -> bool {
+    bindings.is_pressed(Action::Chat)
+}
+
+pub fn listen_debug(bindings: &Bindings) -> bool {
+    bindings.is_pressed(Action::Debug)
+}
+
+pub fn listen_quit(bindings: &Bindings) -> bool {
+    bindings.is_pressed(Action::Quit)
 }
 
-pub fn listen_quit() -> bool {
-    is_key_down(KeyCode::Q) || is_key_down(KeyCode::Escape)
+pub fn listen_bindings(bindings: &Bindings) -> bool {
+    bindings.is_pressed(Action::Bindings)
 }
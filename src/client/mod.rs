@@ -1,2 +1,12 @@
+#[cfg(feature = "client")]
+pub mod bindings;
+pub mod connection;
+#[cfg(feature = "client")]
 pub mod key_event_handler;
+pub mod net_graph;
+#[cfg(feature = "client")]
+pub mod profile;
+pub mod query;
+pub mod reconnect;
 pub mod state;
+pub mod tcp;
@@ -0,0 +1,103 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::config;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    bytes: usize,
+    diff: bool,
+}
+
+/// Sizes of the snapshot diffs received in the last second.
+#[derive(Debug, Clone, Copy)]
+pub struct DiffSizes {
+    pub last: usize,
+    pub average: usize,
+    pub max: usize,
+}
+
+/// Messages received over the last `NET_GRAPH_WINDOW`, for the debug
+/// overlay. Sizes are frames on the wire, header included.
+#[derive(Debug, Default)]
+pub struct NetGraph {
+    samples: VecDeque<Sample>,
+}
+
+impl NetGraph {
+    pub fn record(&mut self, bytes: usize, diff: bool) {
+        let now = Instant::now();
+        self.samples.push_back(Sample {
+            at: now,
+            bytes,
+            diff,
+        });
+        while self
+            .samples
+            .front()
+            .is_some_and(|sample| now.duration_since(sample.at) > config::NET_GRAPH_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn last_second(&self) -> impl Iterator<Item = &Sample> {
+        self.samples
+            .iter()
+            .filter(|sample| sample.at.elapsed() <= Duration::from_secs(1))
+    }
+
+    pub fn bytes_per_second(&self) -> usize {
+        self.last_second().map(|sample| sample.bytes).sum()
+    }
+
+    /// Snapshot diffs received in the last second.
+    pub fn snapshot_rate(&self) -> usize {
+        self.last_second().filter(|sample| sample.diff).count()
+    }
+
+    pub fn diff_sizes(&self) -> Option<DiffSizes> {
+        let sizes: Vec<usize> = self
+            .last_second()
+            .filter(|sample| sample.diff)
+            .map(|sample| sample.bytes)
+            .collect();
+        Some(DiffSizes {
+            last: *sizes.last()?,
+            average: sizes.iter().sum::<usize>() / sizes.len(),
+            max: sizes.iter().copied().max()?,
+        })
+    }
+
+    pub fn since_last_diff(&self) -> Option<Duration> {
+        self.samples
+            .iter()
+            .rev()
+            .find(|sample| sample.diff)
+            .map(|sample| sample.at.elapsed())
+    }
+
+    /// Bytes received in `count` equal slices of the window, oldest first,
+    /// as (snapshot diffs, everything else).
+    pub fn buckets(&self, count: usize) -> Vec<(usize, usize)> {
+        let mut buckets = vec![(0, 0); count];
+        let width = config::NET_GRAPH_WINDOW.as_secs_f32() / count as f32;
+        for sample in &self.samples {
+            let age = sample.at.elapsed().as_secs_f32();
+            let from_end = (age / width) as usize;
+            if from_end >= count {
+                continue;
+            }
+            let bucket = &mut buckets[count - 1 - from_end];
+            if sample.diff {
+                bucket.0 += sample.bytes;
+            } else {
+                bucket.1 += sample.bytes;
+            }
+        }
+        buckets
+    }
+}
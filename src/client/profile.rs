@@ -0,0 +1,78 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentServer {
+    pub address: String,
+    pub tcp_port: u16,
+}
+
+/// Nickname and recently joined servers, kept in `profile.toml` in the user
+/// config dir so the main menu can prefill them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Profile {
+    pub nickname: String,
+    pub recent_servers: Vec<RecentServer>,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        let Some(path) = config_path(config::PROFILE_FILE) else {
+            return Self::default();
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match toml::from_str(&text) {
+            Ok(profile) => profile,
+            Err(e) => {
+                eprintln!("Invalid profile in {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> bool {
+        match toml::to_string(self) {
+            Ok(text) => write_config(config::PROFILE_FILE, &text),
+            Err(e) => {
+                eprintln!("Failed to serialize profile: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Moves `server` to the front of the recent list.
+    pub fn remember_server(&mut self, server: RecentServer) {
+        self.recent_servers.retain(|s| s != &server);
+        self.recent_servers.insert(0, server);
+        self.recent_servers.truncate(config::MAX_RECENT_SERVERS);
+    }
+}
+
+/// Path of `name` in the client's directory under the user config dir.
+pub(crate) fn config_path(name: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(config::CONFIG_DIR_NAME).join(name))
+}
+
+pub(crate) fn write_config(name: &str, text: &str) -> bool {
+    let Some(path) = config_path(name) else {
+        eprintln!("No config directory to save {} to", name);
+        return false;
+    };
+    if let Some(dir) = path.parent()
+        && let Err(e) = fs::create_dir_all(dir)
+    {
+        eprintln!("Failed to create {}: {:?}", dir.display(), e);
+        return false;
+    }
+    if let Err(e) = fs::write(&path, text) {
+        eprintln!("Failed to write {}: {:?}", path.display(), e);
+        return false;
+    }
+    true
+}
@@ -0,0 +1,35 @@
+use std::{net::SocketAddr, time::Duration};
+
+use crate::{
+    network::{
+        packet::{PacketReader, PacketWriter},
+        state::{ClientMessage, ServerInfo, ServerMessage, ServerMessageType},
+    },
+    utils,
+};
+
+const QUERY_ATTEMPTS: u32 = 3;
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub fn query(addr: SocketAddr) -> Option<ServerInfo> {
+    let socket = utils::bind_udp_for(addr).ok()?;
+    socket.set_read_timeout(Some(QUERY_TIMEOUT)).ok()?;
+
+    let mut writer = PacketWriter::new(addr);
+    let mut reader = PacketReader::<ServerMessage>::new();
+    for _ in 0..QUERY_ATTEMPTS {
+        writer.push(&socket, &ClientMessage::Query);
+        writer.flush(&socket);
+
+        while let Some((msg, src)) = reader.recv(&socket) {
+            if src != addr {
+                continue;
+            }
+            if let ServerMessageType::Info(info) = msg.message {
+                return Some(info);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,89 @@
+use std::time::Instant;
+
+use crate::config;
+
+use super::connection::Connection;
+
+/// Replaces a connection that stopped hearing from the server. Once a joined
+/// connection has been silent for `RECONNECT_AFTER`, a new socket rebinds to
+/// the same player with its join token every `RECONNECT_INTERVAL`; the first
+/// one that receives a game state takes over. Gives up after
+/// `RECONNECT_ATTEMPTS`.
+#[derive(Default)]
+pub struct Reconnector {
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    candidate: Option<(Connection, Instant)>,
+}
+
+impl Reconnector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call once per frame. Returns the connection to switch to once it has
+    /// received a game state.
+    pub fn update(&mut self, current: &Connection) -> Option<Connection> {
+        let (joined, silence) = {
+            let state = current.state();
+            (state.game_state.is_some(), state.silence())
+        };
+        if !joined || silence < config::RECONNECT_AFTER {
+            self.reset();
+            return None;
+        }
+
+        let now = Instant::now();
+        if let Some((candidate, started)) = self.candidate.take() {
+            if candidate.state().game_state.is_some() {
+                self.reset();
+                return Some(candidate);
+            }
+            // Dropped without `Quit`: it is bound to the same player.
+            if started.elapsed() < config::RECONNECT_INTERVAL {
+                self.candidate = Some((candidate, started));
+            }
+        }
+
+        let due = self.next_attempt.is_none_or(|at| now >= at);
+        if self.candidate.is_none() && due && self.attempts < config::RECONNECT_ATTEMPTS {
+            self.attempts += 1;
+            self.next_attempt = Some(now + config::RECONNECT_INTERVAL);
+
+            let Some((player_id, token)) = current.join_token() else {
+                // Never got a token, so there is nothing to rebind to.
+                self.attempts = config::RECONNECT_ATTEMPTS;
+                return None;
+            };
+            match Connection::reattach(
+                current.server_addr(),
+                current.tcp_addr(),
+                current.name(),
+                player_id,
+                token,
+            ) {
+                Ok(candidate) => self.candidate = Some((candidate, now)),
+                Err(e) => eprintln!("Reconnect failed: {:?}", e),
+            }
+        }
+        None
+    }
+
+    /// Reconnect attempts started since the connection went silent.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Every attempt has been used up without success.
+    pub fn gave_up(&self) -> bool {
+        self.attempts >= config::RECONNECT_ATTEMPTS
+            && self.candidate.is_none()
+            && self.next_attempt.is_none_or(|at| Instant::now() >= at)
+    }
+
+    fn reset(&mut self) {
+        self.attempts = 0;
+        self.next_attempt = None;
+        self.candidate = None;
+    }
+}
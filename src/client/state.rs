@@ -1,26 +1,124 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
+use super::net_graph::NetGraph;
 use crate::{
+    config,
     game::{
+        event::GameEvent,
         player::Player,
         state::{GameState, GameStateDiff},
     },
     map::Map,
+    network::{
+        reliable::ReliableReceiver,
+        state::{ChatMessage, ScoreboardEntry},
+    },
 };
 
+#[derive(Debug, Clone)]
+pub struct KillFeedEntry {
+    pub killer: String,
+    pub victim: String,
+    pub at: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatEntry {
+    pub message: ChatMessage,
+    pub at: Instant,
+}
+
+impl ChatEntry {
+    /// Opacity: 1.0 until the last `CHAT_FADE_DURATION` of
+    /// `CHAT_MESSAGE_DURATION`, then down to 0.0.
+    pub fn opacity(&self) -> f32 {
+        let left = config::CHAT_MESSAGE_DURATION.saturating_sub(self.at.elapsed());
+        (left.as_secs_f32() / config::CHAT_FADE_DURATION.as_secs_f32()).min(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FloatingTextKind {
+    DamageDealt,
+    DamageTaken,
+    Pickup,
+    Respawn,
+}
+
+/// Text that rises from a map position and fades out.
+#[derive(Debug, Clone)]
+pub struct FloatingText {
+    pub text: String,
+    pub kind: FloatingTextKind,
+    pub x: f32,
+    pub y: f32,
+    pub at: Instant,
+}
+
+impl FloatingText {
+    /// 0.0 when spawned, 1.0 when it disappears.
+    pub fn progress(&self) -> f32 {
+        (self.at.elapsed().as_secs_f32() / config::FLOATING_TEXT_DURATION.as_secs_f32()).min(1.0)
+    }
+}
+
 #[derive(Debug)]
 pub struct ClientState {
     pub id: Option<u32>,
+    /// Rebinds to the player from another socket.
+    pub join_token: Option<u64>,
     pub map: Option<Arc<Mutex<Map>>>,
     pub game_state: Option<Arc<GameState>>,
+    pub last_seq: u32,
+    /// Server tick of the newest diff.
+    pub server_tick: u64,
+    pub received_diffs: u32,
+    pub desynced: bool,
+    pub last_resync: Option<Instant>,
+    pub server_closed: Option<String>,
+    /// When anything last arrived from the server.
+    pub last_packet: Instant,
+    /// `last_seq` at the previous stats report, to count missed diffs.
+    stats_seq: u32,
+    packet_loss: Option<f32>,
+    scoreboard: HashMap<u32, (ScoreboardEntry, Instant)>,
+    player_total: u32,
+    net_graph: NetGraph,
+    events: ReliableReceiver,
+    kill_feed: VecDeque<KillFeedEntry>,
+    floating_texts: Vec<FloatingText>,
+    chat: ReliableReceiver,
+    chat_log: VecDeque<ChatEntry>,
 }
 
 impl ClientState {
     pub fn new() -> Self {
         Self {
             id: None,
+            join_token: None,
             map: None,
             game_state: None,
+            last_seq: 0,
+            server_tick: 0,
+            received_diffs: 0,
+            desynced: false,
+            last_resync: None,
+            server_closed: None,
+            last_packet: Instant::now(),
+            stats_seq: 0,
+            packet_loss: None,
+            scoreboard: HashMap::new(),
+            player_total: 0,
+            net_graph: NetGraph::default(),
+            events: ReliableReceiver::default(),
+            kill_feed: VecDeque::new(),
+            floating_texts: Vec::new(),
+            chat: ReliableReceiver::default(),
+            chat_log: VecDeque::new(),
         }
     }
 
@@ -32,9 +130,19 @@ impl ClientState {
 
     pub fn update_state(&mut self, state: GameState) {
         self.game_state = Some(Arc::new(state));
+        self.desynced = false;
     }
 
     pub fn update_state_diff(&mut self, state_diff: GameStateDiff) {
+        self.received_diffs += 1;
+        // A reordered datagram: everything in it is older than what we have.
+        if (state_diff.seq.wrapping_sub(self.last_seq) as i32) <= 0 {
+            return;
+        }
+        self.last_seq = state_diff.seq;
+        self.server_tick = state_diff.tick;
+        let checksum = state_diff.checksum;
+
         if let Some(gs_arc) = &self.game_state {
             let mut gs_arc_clone = Arc::clone(gs_arc);
             let gs = Arc::make_mut(&mut gs_arc_clone);
@@ -81,6 +189,60 @@ impl ClientState {
 
             self.game_state = Some(Arc::new(gs));
         }
+
+        if let (Some(expected), Some(gs)) = (checksum, &self.game_state)
+            && gs.checksum() != expected
+        {
+            self.desynced = true;
+        }
+    }
+
+    /// True when the last checksum did not match and no resync was requested recently.
+    pub fn take_resync_request(&mut self) -> bool {
+        if !self.desynced {
+            return false;
+        }
+        if self
+            .last_resync
+            .is_some_and(|at| at.elapsed() < config::RESYNC_COOLDOWN)
+        {
+            return false;
+        }
+        self.last_resync = Some(Instant::now());
+        true
+    }
+
+    /// Latest sequence number and diffs received since the last call. Also
+    /// updates `packet_loss` for that window.
+    pub fn take_net_stats(&mut self) -> (u32, u32) {
+        let received = self.received_diffs;
+        self.received_diffs = 0;
+
+        let expected = self.last_seq.wrapping_sub(self.stats_seq);
+        if (expected as i32) > 0 {
+            self.packet_loss = Some(1.0 - received.min(expected) as f32 / expected as f32);
+        }
+        self.stats_seq = self.last_seq;
+        (self.last_seq, received)
+    }
+
+    /// Share of snapshot diffs lost in the last stats window.
+    pub fn packet_loss(&self) -> Option<f32> {
+        self.packet_loss
+    }
+
+    /// Counts a received message of `bytes` for the debug overlay.
+    pub fn record_received(&mut self, bytes: usize, diff: bool) {
+        self.net_graph.record(bytes, diff);
+    }
+
+    pub fn net_graph(&self) -> &NetGraph {
+        &self.net_graph
+    }
+
+    /// Time since anything arrived from the server.
+    pub fn silence(&self) -> Duration {
+        self.last_packet.elapsed()
     }
 
     pub fn get_current_player(&self) -> Option<Player> {
@@ -90,4 +252,221 @@ impl ClientState {
             None
         }
     }
+
+    pub fn update_scoreboard(&mut self, total: u32, entries: Vec<ScoreboardEntry>) {
+        let now = Instant::now();
+        self.player_total = total;
+        for entry in entries {
+            self.scoreboard.insert(entry.id, (entry, now));
+        }
+        // Players that left stop appearing in the pages.
+        self.scoreboard
+            .retain(|_, (_, seen)| now.duration_since(*seen) < config::SCOREBOARD_INTERVAL * 3);
+    }
+
+    /// Every player on the server, best score first.
+    pub fn scoreboard(&self) -> Vec<ScoreboardEntry> {
+        let mut entries: Vec<_> = self
+            .scoreboard
+            .values()
+            .map(|(entry, _)| entry.clone())
+            .collect();
+        entries.sort_by(|a, b| {
+            b.score()
+                .cmp(&a.score())
+                .then(a.deths.cmp(&b.deths))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        entries
+    }
+
+    /// Players on the server as of the last scoreboard page, never fewer
+    /// than are visible right now.
+    pub fn player_total(&self) -> usize {
+        let visible = self
+            .game_state
+            .as_ref()
+            .map_or(0, |gs| gs.players.values().filter(|p| p.to_render).count());
+        (self.player_total as usize).max(visible)
+    }
+
+    /// Round trip time measured by the server, if known yet.
+    pub fn ping_ms(&self) -> Option<u32> {
+        let id = self.id?;
+        self.scoreboard
+            .get(&id)
+            .and_then(|(entry, _)| entry.ping_ms)
+    }
+
+    /// Applies a batch of game events and returns the sequence number to
+    /// acknowledge.
+    pub fn receive_events(&mut self, first_seq: u32, events: Vec<GameEvent>) -> u32 {
+        let now = Instant::now();
+        for event in self.events.accept(first_seq, events) {
+            self.apply_event(event, now);
+        }
+        self.kill_feed
+            .retain(|entry| now.duration_since(entry.at) < config::KILL_FEED_DURATION);
+        self.floating_texts
+            .retain(|text| now.duration_since(text.at) < config::FLOATING_TEXT_DURATION);
+        self.events.next_expected()
+    }
+
+    fn apply_event(&mut self, event: GameEvent, now: Instant) {
+        let own = |id: u32| self.id == Some(id);
+        let floating = match event {
+            GameEvent::Kill { killer, victim } => {
+                self.kill_feed.push_back(KillFeedEntry {
+                    killer: self.player_name(killer),
+                    victim: self.player_name(victim),
+                    at: now,
+                });
+                if self.kill_feed.len() > config::KILL_FEED_SIZE {
+                    self.kill_feed.pop_front();
+                }
+                None
+            }
+            GameEvent::Damage {
+                victim,
+                amount,
+                x,
+                y,
+                ..
+            } => {
+                let kind = if own(victim) {
+                    FloatingTextKind::DamageTaken
+                } else {
+                    FloatingTextKind::DamageDealt
+                };
+                Some((format!("-{}", amount), kind, x, y))
+            }
+            GameEvent::Pickup { player, kind, x, y } if own(player) => {
+                Some((kind.label(), FloatingTextKind::Pickup, x, y))
+            }
+            GameEvent::Respawn { player, x, y } if own(player) => {
+                Some(("respawned".to_string(), FloatingTextKind::Respawn, x, y))
+            }
+            GameEvent::Pickup { .. } | GameEvent::Respawn { .. } => None,
+        };
+
+        if let Some((text, kind, x, y)) = floating {
+            self.floating_texts.push(FloatingText {
+                text,
+                kind,
+                x,
+                y,
+                at: now,
+            });
+        }
+    }
+
+    /// Name from the game state or the scoreboard, `#id` if neither knows it.
+    fn player_name(&self, id: u32) -> String {
+        self.game_state
+            .as_ref()
+            .and_then(|gs| gs.players.get(&id))
+            .map(|player| player.name.clone())
+            .or_else(|| {
+                self.scoreboard
+                    .get(&id)
+                    .map(|(entry, _)| entry.name.clone())
+            })
+            .unwrap_or_else(|| format!("#{}", id))
+    }
+
+    /// Recent kills, oldest first.
+    pub fn kill_feed(&self) -> Vec<KillFeedEntry> {
+        self.kill_feed
+            .iter()
+            .filter(|entry| entry.at.elapsed() < config::KILL_FEED_DURATION)
+            .cloned()
+            .collect()
+    }
+
+    pub fn floating_texts(&self) -> Vec<FloatingText> {
+        self.floating_texts
+            .iter()
+            .filter(|text| text.progress() < 1.0)
+            .cloned()
+            .collect()
+    }
+
+    /// Adds a batch of chat messages to the log and returns the sequence
+    /// number to acknowledge.
+    pub fn receive_chat(&mut self, first_seq: u32, messages: Vec<ChatMessage>) -> u32 {
+        let now = Instant::now();
+        for message in self.chat.accept(first_seq, messages) {
+            self.chat_log.push_back(ChatEntry { message, at: now });
+            if self.chat_log.len() > config::CHAT_LOG_SIZE {
+                self.chat_log.pop_front();
+            }
+        }
+        self.chat.next_expected()
+    }
+
+    /// The last `CHAT_LOG_SIZE` chat messages, oldest first, faded out or not.
+    pub fn chat_log(&self) -> Vec<ChatEntry> {
+        self.chat_log.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(seq: u32, player: Player) -> GameStateDiff {
+        GameStateDiff {
+            seq,
+            tick: seq as u64,
+            checksum: None,
+            players: HashMap::from([(player.id, player)]),
+            removed_players: Vec::new(),
+            bullets: HashMap::new(),
+            removed_bullets: Vec::new(),
+            modifieres: HashMap::new(),
+            removed_modifieres: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn reordered_diff_is_ignored() {
+        let mut state = ClientState::new();
+        state.update_state(GameState::new());
+
+        state.update_state_diff(diff(2, Player::new(1, "a".into(), 5.0, 5.0)));
+        state.update_state_diff(diff(1, Player::new(1, "a".into(), 1.0, 1.0)));
+
+        let gs = state.game_state.as_ref().unwrap();
+        assert_eq!(gs.players[&1].x, 5.0);
+        assert_eq!((state.last_seq, state.server_tick), (2, 2));
+        assert_eq!(state.received_diffs, 2);
+        assert!(!state.desynced);
+    }
+
+    #[test]
+    fn duplicate_diff_is_ignored() {
+        let mut state = ClientState::new();
+        state.update_state(GameState::new());
+
+        state.update_state_diff(diff(3, Player::new(1, "a".into(), 5.0, 5.0)));
+        let mut stale = diff(3, Player::new(2, "b".into(), 1.0, 1.0));
+        stale.checksum = Some(0);
+        state.update_state_diff(stale);
+
+        let gs = state.game_state.as_ref().unwrap();
+        assert!(!gs.players.contains_key(&2));
+        assert!(!state.desynced);
+    }
+
+    #[test]
+    fn newer_diff_after_wraparound_applies() {
+        let mut state = ClientState::new();
+        state.update_state(GameState::new());
+        state.last_seq = u32::MAX;
+
+        state.update_state_diff(diff(0, Player::new(1, "a".into(), 5.0, 5.0)));
+
+        assert_eq!(state.last_seq, 0);
+        assert!(state.game_state.as_ref().unwrap().players.contains_key(&1));
+    }
 }
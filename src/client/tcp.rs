@@ -0,0 +1,81 @@
+use std::{
+    net::{SocketAddr, TcpStream},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{
+    game::player::Player,
+    network::{
+        recv_tcp_message, send_tcp_message,
+        state::{AdminCommand, ClientMessage, MapDownloader, ServerMessageType},
+    },
+};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn connect(addr: SocketAddr) -> Option<TcpStream> {
+    let stream = match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("Failed to connect to {}: {:?}", addr, e);
+            return None;
+        }
+    };
+    stream.set_read_timeout(Some(READ_TIMEOUT)).ok()?;
+    stream.set_nodelay(true).ok()?;
+
+    Some(stream)
+}
+
+pub fn join(addr: SocketAddr, name: &str) -> Option<(Player, u64)> {
+    let mut stream = connect(addr)?;
+    if !send_tcp_message(&mut stream, &ClientMessage::Init(name.to_string())) {
+        return None;
+    }
+
+    match recv_tcp_message::<ServerMessageType>(&mut stream)? {
+        ServerMessageType::Welcome { player, token } => Some((*player, token)),
+        other => {
+            eprintln!("Unexpected join response: {:?}", other);
+            None
+        }
+    }
+}
+
+pub fn download_map(addr: SocketAddr, map_downloader: &Mutex<MapDownloader>) -> bool {
+    let Some(mut stream) = connect(addr) else {
+        return false;
+    };
+
+    let chunk_ids = map_downloader.lock().unwrap().get_exist_chunk_id();
+    if !send_tcp_message(&mut stream, &ClientMessage::Map(chunk_ids)) {
+        return false;
+    }
+
+    while let Some(msg) = recv_tcp_message::<ServerMessageType>(&mut stream) {
+        if let ServerMessageType::Map(chunk) = msg
+            && map_downloader.lock().unwrap().load_chunk(chunk)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub fn admin(addr: SocketAddr, command: AdminCommand) -> Option<String> {
+    let mut stream = connect(addr)?;
+    if !send_tcp_message(&mut stream, &ClientMessage::Admin(command)) {
+        return None;
+    }
+
+    match recv_tcp_message::<ServerMessageType>(&mut stream)? {
+        ServerMessageType::Admin(response) => Some(response),
+        other => {
+            eprintln!("Unexpected admin response: {:?}", other);
+            None
+        }
+    }
+}
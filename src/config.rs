@@ -13,6 +13,78 @@ pub const STEP: f32 = 2.0;
 pub const HIT_RADIUS: f32 = 0.5;
 pub const FIRE_RATE: f32 = 0.8;
 pub const MODIFIER_RESPAWN_TIME: Duration = Duration::from_secs(13);
+pub const MODIFIER_PICKUP_RADIUS: f32 = 1.0;
+pub const GRID_CELL_SIZE: f32 = 8.0;
+pub const DEFAULT_VIEW_HALF_EXTENT: f32 = 50.0;
+pub const MAX_VIEW_HALF_WIDTH: f32 = 80.0;
+pub const MAX_VIEW_HALF_HEIGHT: f32 = 50.0;
+pub const VIEW_MARGIN: f32 = 2.0;
 
 pub const UDP_PORT: usize = 8888;
 pub const TCP_PORT: usize = 8887;
+pub const DISCOVERY_PORT: usize = 8886;
+pub const REGISTRY_PORT: usize = 8885;
+pub const REGISTRY_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+pub const REGISTRY_SERVER_TTL: Duration = Duration::from_secs(15);
+pub const DISCOVERY_TIMEOUT: Duration = Duration::from_millis(700);
+pub const SERVER_NAME: &str = "termarena";
+pub const GAME_MODE: &str = "deathmatch";
+pub const MAP_UDP_MAX_FAILED_ATTEMPTS: u32 = 3;
+pub const MAP_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+pub const MAX_TCP_CLIENTS: usize = 32;
+pub const TCP_IO_TIMEOUT: Duration = Duration::from_secs(10);
+pub const JOIN_BIND_TIMEOUT: Duration = Duration::from_secs(10);
+pub const HELLO_TTL: Duration = Duration::from_secs(10);
+pub const MAX_PENDING_HELLOS: usize = 1024;
+pub const TICK_RATE: u32 = 60;
+pub const SNAPSHOT_RATE: u32 = 30;
+pub const MIN_SNAPSHOT_RATE: f32 = 5.0;
+pub const SNAPSHOT_RATE_INCREASE: f32 = 2.0;
+pub const SNAPSHOT_RATE_DECREASE: f32 = 0.5;
+pub const SNAPSHOT_LOSS_THRESHOLD: f32 = 0.05;
+pub const NET_STATS_INTERVAL: Duration = Duration::from_secs(1);
+pub const INPUT_QUEUE_SIZE: usize = 1024;
+pub const CLIENT_QUEUE_SIZE: usize = 64;
+pub const MISC_QUEUE_SIZE: usize = 1024;
+pub const PACKET_MTU: usize = 1200;
+pub const COMPRESSION_THRESHOLD: usize = 256;
+pub const CHECKSUM_INTERVAL: u32 = 10;
+pub const RESYNC_COOLDOWN: Duration = Duration::from_secs(1);
+pub const SHUTDOWN_NOTICE_REPEATS: u32 = 3;
+pub const SHUTDOWN_NOTICE_INTERVAL: Duration = Duration::from_millis(50);
+pub const SERVER_POLL_INTERVAL: Duration = Duration::from_millis(100);
+pub const CLIENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+pub const EVENT_QUEUE_SIZE: usize = 1024;
+pub const TUI_TILE_COLUMNS: u16 = 2;
+pub const TUI_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+pub const TUI_KEY_HOLD: Duration = Duration::from_millis(200);
+pub const CONFIG_DIR_NAME: &str = "termarena";
+pub const BINDINGS_FILE: &str = "bindings.toml";
+pub const PROFILE_FILE: &str = "profile.toml";
+pub const MAX_RECENT_SERVERS: usize = 8;
+pub const MAX_NICKNAME_LEN: usize = 16;
+pub const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+pub const CONNECTION_INTERRUPTED_AFTER: Duration = Duration::from_secs(2);
+pub const RECONNECT_AFTER: Duration = Duration::from_secs(5);
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(3);
+pub const RECONNECT_ATTEMPTS: u32 = 5;
+pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+pub const NET_GRAPH_WINDOW: Duration = Duration::from_secs(5);
+pub const SCOREBOARD_INTERVAL: Duration = Duration::from_secs(1);
+pub const SCOREBOARD_PAGE_SIZE: usize = 16;
+pub const PING_INTERVAL: Duration = Duration::from_secs(1);
+pub const EVENT_RESEND_INTERVAL: Duration = Duration::from_millis(150);
+pub const EVENT_PAGE_SIZE: usize = 32;
+pub const MAX_PENDING_EVENTS: usize = 256;
+pub const KILL_FEED_DURATION: Duration = Duration::from_secs(5);
+pub const KILL_FEED_SIZE: usize = 5;
+pub const FLOATING_TEXT_DURATION: Duration = Duration::from_millis(1000);
+pub const MAX_CHAT_LEN: usize = 120;
+pub const CHAT_RATE_LIMIT: usize = 5;
+pub const CHAT_RATE_WINDOW: Duration = Duration::from_secs(10);
+pub const CHAT_RESEND_INTERVAL: Duration = Duration::from_millis(150);
+pub const CHAT_PAGE_SIZE: usize = 4;
+pub const MAX_PENDING_CHAT: usize = 64;
+pub const CHAT_LOG_SIZE: usize = 8;
+pub const CHAT_MESSAGE_DURATION: Duration = Duration::from_secs(10);
+pub const CHAT_FADE_DURATION: Duration = Duration::from_secs(2);
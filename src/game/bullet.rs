@@ -1,8 +1,5 @@
-use macroquad::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::config;
-
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Bullet {
     pub id: u32,
@@ -21,16 +18,3 @@ pub struct Bullet {
     #[serde(skip)]
     pub render_y: f32,
 }
-
-impl Bullet {
-    pub fn render(&self, offset_x: f32, offset_y: f32) {
-        let draw_x = self.x * config::TILE_SIZE + offset_x;
-        let draw_y = self.y * config::TILE_SIZE + offset_y;
-        draw_circle(
-            draw_x,
-            draw_y,
-            self.hit_radius * config::TILE_SIZE,
-            DARKPURPLE,
-        );
-    }
-}
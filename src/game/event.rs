@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+
+use super::modifier::ModifierKind;
+
+/// Something that happened during `GameState::update`, delivered reliably
+/// to the clients it concerns.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Kill {
+        killer: u32,
+        victim: u32,
+    },
+    Damage {
+        attacker: u32,
+        victim: u32,
+        amount: u32,
+        x: f32,
+        y: f32,
+    },
+    Pickup {
+        player: u32,
+        kind: ModifierKind,
+        x: f32,
+        y: f32,
+    },
+    Respawn {
+        player: u32,
+        x: f32,
+        y: f32,
+    },
+}
+
+impl GameEvent {
+    /// Kills go to everyone for the kill feed, the rest only to the players
+    /// involved.
+    pub fn concerns(&self, player_id: u32) -> bool {
+        match self {
+            GameEvent::Kill { .. } => true,
+            GameEvent::Damage {
+                attacker, victim, ..
+            } => *attacker == player_id || *victim == player_id,
+            GameEvent::Pickup { player, .. } | GameEvent::Respawn { player, .. } => {
+                *player == player_id
+            }
+        }
+    }
+}
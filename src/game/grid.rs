@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use crate::config;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<u32>>,
+    entries: HashMap<u32, (i32, i32)>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(config::GRID_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn cell_of(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn update(&mut self, id: u32, x: f32, y: f32) {
+        let cell = self.cell_of(x, y);
+        match self.entries.insert(id, cell) {
+            Some(old) if old == cell => return,
+            Some(old) => self.remove_from_cell(id, old),
+            None => {}
+        }
+        self.cells.entry(cell).or_default().push(id);
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        if let Some(cell) = self.entries.remove(&id) {
+            self.remove_from_cell(id, cell);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    fn remove_from_cell(&mut self, id: u32, cell: (i32, i32)) {
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|&other| other != id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    pub fn query_rect(&self, x: f32, y: f32, half_w: f32, half_h: f32) -> Vec<u32> {
+        let (min_cx, min_cy) = self.cell_of(x - half_w, y - half_h);
+        let (max_cx, max_cy) = self.cell_of(x + half_w, y + half_h);
+
+        let mut ids = Vec::new();
+        for cy in min_cy..=max_cy {
+            for cx in min_cx..=max_cx {
+                if let Some(cell) = self.cells.get(&(cx, cy)) {
+                    ids.extend_from_slice(cell);
+                }
+            }
+        }
+        ids
+    }
+
+    pub fn query_radius(&self, x: f32, y: f32, radius: f32) -> Vec<u32> {
+        self.query_rect(x, y, radius, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut ids: Vec<u32>) -> Vec<u32> {
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn update_moves_entity_across_cells() {
+        let mut grid = SpatialGrid::new(8.0);
+        grid.update(1, 4.0, 4.0);
+        grid.update(1, 4.5, 4.5);
+        assert_eq!(grid.cells[&(0, 0)], vec![1]);
+
+        grid.update(1, 12.0, 4.0);
+        assert!(!grid.cells.contains_key(&(0, 0)));
+        assert_eq!(grid.cells[&(1, 0)], vec![1]);
+        assert_eq!(grid.query_rect(4.0, 4.0, 1.0, 1.0), Vec::<u32>::new());
+        assert_eq!(grid.query_rect(12.0, 4.0, 1.0, 1.0), vec![1]);
+    }
+
+    #[test]
+    fn remove_forgets_entity() {
+        let mut grid = SpatialGrid::new(8.0);
+        grid.update(1, 4.0, 4.0);
+        grid.update(2, 5.0, 5.0);
+        grid.remove(1);
+        grid.remove(3);
+
+        assert_eq!(grid.query_rect(4.0, 4.0, 1.0, 1.0), vec![2]);
+        grid.remove(2);
+        assert!(grid.cells.is_empty() && grid.entries.is_empty());
+    }
+
+    #[test]
+    fn query_rect_includes_cells_touching_its_edges() {
+        let mut grid = SpatialGrid::new(8.0);
+        grid.update(1, 8.0, 0.0); // first point of cell (1, 0)
+        grid.update(2, 7.9, 0.0); // last cell before it
+        grid.update(3, -0.1, 0.0); // cell (-1, 0)
+
+        assert_eq!(sorted(grid.query_rect(4.0, 4.0, 4.0, 4.0)), vec![1, 2]);
+        assert_eq!(grid.query_rect(4.0, 4.0, 3.9, 3.9), vec![2]);
+        assert_eq!(sorted(grid.query_rect(0.0, 0.0, 0.1, 0.1)), vec![2, 3]);
+    }
+
+    #[test]
+    fn query_radius_reaches_players_at_hit_distance() {
+        let reach = config::PLAYER_RADIUS + config::HIT_RADIUS;
+        let mut grid = SpatialGrid::new(config::GRID_CELL_SIZE);
+        let edge = config::GRID_CELL_SIZE;
+        grid.update(1, edge, 4.0);
+
+        // A bullet exactly `reach` away in the neighbouring cell.
+        assert_eq!(grid.query_radius(edge - reach, 4.0, reach), vec![1]);
+        assert_eq!(
+            grid.query_radius(edge - reach - 0.1, 4.0, reach),
+            Vec::<u32>::new()
+        );
+        assert_eq!(grid.query_radius(edge + reach, 4.0, reach), vec![1]);
+    }
+}
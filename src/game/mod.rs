@@ -1,4 +1,8 @@
 pub mod bullet;
+pub mod event;
+pub mod grid;
 pub mod modifier;
 pub mod player;
+#[cfg(feature = "client")]
+mod render;
 pub mod state;
@@ -1,6 +1,4 @@
-use crate::config;
 use ::rand::Rng;
-use macroquad::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -20,21 +18,6 @@ pub struct Modifier {
     pub kind: ModifierKind,
 }
 
-impl Modifier {
-    pub fn render(&self, offset_x: f32, offset_y: f32) {
-        let draw_x = self.x * config::TILE_SIZE + offset_x;
-        let draw_y = self.y * config::TILE_SIZE + offset_y;
-        let color = match self.kind {
-            ModifierKind::Heal(_) => RED,
-            ModifierKind::Damage(_) => MAGENTA,
-            ModifierKind::Speed(_) => GREEN,
-            ModifierKind::FireRate(_) => LIME,
-            ModifierKind::BulletRange(_) => SKYBLUE,
-        };
-        draw_circle(draw_x, draw_y, config::TILE_SIZE * 0.5, color);
-    }
-}
-
 impl ModifierKind {
     pub fn random(rng: &mut impl Rng) -> Self {
         let choice = rng.gen_range(0..5);
@@ -46,4 +29,15 @@ impl ModifierKind {
             _ => ModifierKind::FireRate(0.5),
         }
     }
+
+    /// Short description shown when the modifier is picked up.
+    pub fn label(&self) -> String {
+        match self {
+            ModifierKind::Heal(health) => format!("+{} health", health),
+            ModifierKind::Speed(speed) => format!("+{} speed", speed),
+            ModifierKind::Damage(damage) => format!("+{} damage", damage),
+            ModifierKind::FireRate(fire_rate) => format!("+{} fire rate", fire_rate),
+            ModifierKind::BulletRange(range) => format!("+{} range", range),
+        }
+    }
 }
@@ -1,4 +1,3 @@
-use macroquad::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, Instant};
 
@@ -9,6 +8,7 @@ use super::{bullet::Bullet, state::Direction};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Player {
     pub id: u32,
+    pub name: String,
     pub x: f32,
     pub y: f32,
     pub kills: u32,
@@ -32,9 +32,10 @@ pub struct Player {
 }
 
 impl Player {
-    pub fn new(id: u32, x: f32, y: f32) -> Self {
+    pub fn new(id: u32, name: String, x: f32, y: f32) -> Self {
         Self {
             id,
+            name,
             x,
             y,
             kills: 0,
@@ -60,6 +61,23 @@ impl Player {
         Instant::now() - Duration::from_secs(5)
     }
 
+    /// Where the player will be after `elapsed` seconds if it keeps walking
+    /// towards `move_target`, ignoring walls.
+    pub fn extrapolate(&self, elapsed: f32) -> (f32, f32) {
+        let Some((tx, ty)) = self.move_target.filter(|_| self.is_moving) else {
+            return (self.x, self.y);
+        };
+        let dx = tx - self.x;
+        let dy = ty - self.y;
+        let dist = (dx * dx + dy * dy).sqrt();
+        let step = self.walk_speed * elapsed;
+        if dist <= step {
+            (tx, ty)
+        } else {
+            (self.x + step * dx / dist, self.y + step * dy / dist)
+        }
+    }
+
     pub fn hit_by(&mut self, bullet: &Bullet) -> bool {
         let dx = bullet.x - self.x;
         let dy = bullet.y - self.y;
@@ -73,77 +91,47 @@ impl Player {
             false
         }
     }
+}
 
-    pub fn render(&self, current_id: Option<u32>, offset_x: f32, offset_y: f32) {
-        if !self.to_render {
-            return;
-        }
-        let draw_x = self.x * config::TILE_SIZE + offset_x;
-        let draw_y = self.y * config::TILE_SIZE + offset_y;
-
-        let color = if Some(self.id) == current_id {
-            BLUE
-        } else {
-            DARKBLUE
-        };
-        draw_circle(draw_x, draw_y, self.radius * config::TILE_SIZE, color);
-
-        let tip_length = self.radius * config::TILE_SIZE * 1.5;
-        let tip_width = self.radius * config::TILE_SIZE * 2.0;
-
-        let (tip, left, right) = match self.direction {
-            Direction::Up => (
-                vec2(draw_x, draw_y - tip_length),
-                vec2(draw_x - tip_width / 2.0, draw_y),
-                vec2(draw_x + tip_width / 2.0, draw_y),
-            ),
-            Direction::Down => (
-                vec2(draw_x, draw_y + tip_length),
-                vec2(draw_x - tip_width / 2.0, draw_y),
-                vec2(draw_x + tip_width / 2.0, draw_y),
-            ),
-            Direction::Left => (
-                vec2(draw_x - tip_length, draw_y),
-                vec2(draw_x, draw_y - tip_width / 2.0),
-                vec2(draw_x, draw_y + tip_width / 2.0),
-            ),
-            Direction::Right => (
-                vec2(draw_x + tip_length, draw_y),
-                vec2(draw_x, draw_y - tip_width / 2.0),
-                vec2(draw_x, draw_y + tip_width / 2.0),
-            ),
-        };
-
-        draw_triangle(tip, left, right, color);
-
-        let bar_width = config::TILE_SIZE * 2.0;
-        let bar_height = 4.0;
-        let health_ratio = self.health as f32 / self.max_health as f32;
-        let bar_width_coef = 2.0;
-        let draw_y_coef = 0.6;
+/// Keeps ASCII letters, digits, `_`, `-` and `.`, turns spaces into `_` and
+/// cuts the result to `MAX_NICKNAME_LEN`. Falls back to `player`.
+pub fn sanitize_name(raw: &str) -> String {
+    let name: String = raw
+        .trim()
+        .chars()
+        .map(|c| if c == ' ' { '_' } else { c })
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .take(config::MAX_NICKNAME_LEN)
+        .collect();
+
+    if name.is_empty() {
+        "player".to_string()
+    } else {
+        name
+    }
+}
 
-        draw_rectangle(
-            draw_x - bar_width / bar_width_coef,
-            draw_y + config::TILE_SIZE / draw_y_coef,
-            bar_width,
-            bar_height,
-            RED,
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        draw_rectangle(
-            draw_x - bar_width / bar_width_coef,
-            draw_y + config::TILE_SIZE / draw_y_coef,
-            bar_width * health_ratio,
-            bar_height,
-            GREEN,
-        );
+    #[test]
+    fn sanitize_name_strips_control_characters() {
+        assert_eq!(sanitize_name("  bo\u{1b}[31mb\n\t"), "bo31mb");
+        assert_eq!(sanitize_name("red fox"), "red_fox");
+        assert_eq!(sanitize_name("a.b-c_d"), "a.b-c_d");
+    }
 
-        let text = format!("{}", self.id);
-        let font_size = (self.radius * config::TILE_SIZE) as f32;
-        let text_dimensions = measure_text(&text, None, font_size as u16, 1.0);
-        let text_x = draw_x - text_dimensions.width / 2.0;
-        let text_y = draw_y + text_dimensions.height / 2.0;
+    #[test]
+    fn sanitize_name_falls_back_when_nothing_is_left() {
+        assert_eq!(sanitize_name(""), "player");
+        assert_eq!(sanitize_name(" \u{7}\u{0}\r\n "), "player");
+        assert_eq!(sanitize_name("ёжик"), "player");
+    }
 
-        draw_text(&text, text_x, text_y, font_size, SKYBLUE);
+    #[test]
+    fn sanitize_name_caps_the_length() {
+        let name = sanitize_name(&"x".repeat(config::MAX_NICKNAME_LEN + 5));
+        assert_eq!(name, "x".repeat(config::MAX_NICKNAME_LEN));
     }
 }
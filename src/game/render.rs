@@ -0,0 +1,208 @@
+use macroquad::prelude::*;
+
+use crate::config;
+
+use super::{
+    bullet::Bullet,
+    modifier::{Modifier, ModifierKind},
+    player::Player,
+    state::{Direction, GameState},
+};
+
+impl GameState {
+    pub fn render(&self, current_id: Option<u32>, player_pos: (f32, f32)) {
+        let offset_x = screen_width() / 2.0 - player_pos.0 * config::TILE_SIZE;
+        let offset_y = screen_height() / 2.0 - player_pos.1 * config::TILE_SIZE;
+
+        for player in self.players.values() {
+            if Some(player.id) == current_id {
+                player.render(current_id, offset_x, offset_y);
+                continue;
+            }
+
+            let dx = player.x - player_pos.0;
+            let dy = player.y - player_pos.1;
+
+            let screen_x = screen_width() / 2.0 + dx * config::TILE_SIZE;
+            let screen_y = screen_height() / 2.0 + dy * config::TILE_SIZE;
+
+            if screen_x < 0.0
+                || screen_x > screen_width()
+                || screen_y < 0.0
+                || screen_y > screen_height()
+            {
+                self.draw_offscreen_arrow(dx, dy);
+            } else {
+                player.render(current_id, offset_x, offset_y);
+            }
+        }
+
+        for bullet in self.bullets.values() {
+            bullet.render(offset_x, offset_y);
+        }
+
+        for modifier in self.modifieres.values() {
+            modifier.render(offset_x, offset_y);
+        }
+
+        self.render_hud(current_id);
+    }
+
+    pub fn draw_offscreen_arrow(&self, dx: f32, dy: f32) {
+        let angle = dy.atan2(dx);
+        let margin = 20.0;
+        let half_w = screen_width() / 2.0 - margin;
+        let half_h = screen_height() / 2.0 - margin;
+
+        let mut arrow_x = screen_width() / 2.0 + half_w * angle.cos();
+        let mut arrow_y = screen_height() / 2.0 + half_h * angle.sin();
+
+        arrow_x = arrow_x.clamp(margin, screen_width() - margin);
+        arrow_y = arrow_y.clamp(margin, screen_height() - margin);
+
+        let arrow_size = 10.0;
+        let angle_offset = std::f32::consts::PI / 6.0;
+
+        let tip = Vec2::new(arrow_x, arrow_y);
+        let left = Vec2::new(
+            arrow_x - arrow_size * (angle - angle_offset).cos(),
+            arrow_y - arrow_size * (angle - angle_offset).sin(),
+        );
+        let right = Vec2::new(
+            arrow_x - arrow_size * (angle + angle_offset).cos(),
+            arrow_y - arrow_size * (angle + angle_offset).sin(),
+        );
+
+        draw_triangle(tip, left, right, RED);
+    }
+
+    /// Compact status of the local player; the full list is the scoreboard.
+    pub fn render_hud(&self, current_id: Option<u32>) {
+        let Some(player) = current_id.and_then(|id| self.players.get(&id)) else {
+            return;
+        };
+
+        draw_text(
+            &format!(
+                "{} | Health {}/{} | Kills: {} | Deths: {}",
+                player.name, player.health, player.max_health, player.kills, player.deths,
+            ),
+            10.0,
+            20.0,
+            20.0,
+            WHITE,
+        );
+        draw_text(
+            &format!(
+                "Damage: {} | Fire rate: {:.2} | Speed: {}",
+                player.bullet_damage, player.fire_rate, player.walk_speed,
+            ),
+            10.0,
+            42.0,
+            18.0,
+            LIGHTGRAY,
+        );
+    }
+}
+
+impl Player {
+    pub fn render(&self, current_id: Option<u32>, offset_x: f32, offset_y: f32) {
+        if !self.to_render {
+            return;
+        }
+        let draw_x = self.x * config::TILE_SIZE + offset_x;
+        let draw_y = self.y * config::TILE_SIZE + offset_y;
+
+        let color = if Some(self.id) == current_id {
+            BLUE
+        } else {
+            DARKBLUE
+        };
+        draw_circle(draw_x, draw_y, self.radius * config::TILE_SIZE, color);
+
+        let tip_length = self.radius * config::TILE_SIZE * 1.5;
+        let tip_width = self.radius * config::TILE_SIZE * 2.0;
+
+        let (tip, left, right) = match self.direction {
+            Direction::Up => (
+                vec2(draw_x, draw_y - tip_length),
+                vec2(draw_x - tip_width / 2.0, draw_y),
+                vec2(draw_x + tip_width / 2.0, draw_y),
+            ),
+            Direction::Down => (
+                vec2(draw_x, draw_y + tip_length),
+                vec2(draw_x - tip_width / 2.0, draw_y),
+                vec2(draw_x + tip_width / 2.0, draw_y),
+            ),
+            Direction::Left => (
+                vec2(draw_x - tip_length, draw_y),
+                vec2(draw_x, draw_y - tip_width / 2.0),
+                vec2(draw_x, draw_y + tip_width / 2.0),
+            ),
+            Direction::Right => (
+                vec2(draw_x + tip_length, draw_y),
+                vec2(draw_x, draw_y - tip_width / 2.0),
+                vec2(draw_x, draw_y + tip_width / 2.0),
+            ),
+        };
+
+        draw_triangle(tip, left, right, color);
+
+        let bar_width = config::TILE_SIZE * 2.0;
+        let bar_height = 4.0;
+        let health_ratio = self.health as f32 / self.max_health as f32;
+        let bar_width_coef = 2.0;
+        let draw_y_coef = 0.6;
+
+        draw_rectangle(
+            draw_x - bar_width / bar_width_coef,
+            draw_y + config::TILE_SIZE / draw_y_coef,
+            bar_width,
+            bar_height,
+            RED,
+        );
+
+        draw_rectangle(
+            draw_x - bar_width / bar_width_coef,
+            draw_y + config::TILE_SIZE / draw_y_coef,
+            bar_width * health_ratio,
+            bar_height,
+            GREEN,
+        );
+
+        let font_size = 16.0;
+        let text_dimensions = measure_text(&self.name, None, font_size as u16, 1.0);
+        let text_x = draw_x - text_dimensions.width / 2.0;
+        let text_y = draw_y - self.radius * config::TILE_SIZE * 2.0 - 4.0;
+
+        draw_text(&self.name, text_x, text_y, font_size, SKYBLUE);
+    }
+}
+
+impl Bullet {
+    pub fn render(&self, offset_x: f32, offset_y: f32) {
+        let draw_x = self.x * config::TILE_SIZE + offset_x;
+        let draw_y = self.y * config::TILE_SIZE + offset_y;
+        draw_circle(
+            draw_x,
+            draw_y,
+            self.hit_radius * config::TILE_SIZE,
+            DARKPURPLE,
+        );
+    }
+}
+
+impl Modifier {
+    pub fn render(&self, offset_x: f32, offset_y: f32) {
+        let draw_x = self.x * config::TILE_SIZE + offset_x;
+        let draw_y = self.y * config::TILE_SIZE + offset_y;
+        let color = match self.kind {
+            ModifierKind::Heal(_) => RED,
+            ModifierKind::Damage(_) => MAGENTA,
+            ModifierKind::Speed(_) => GREEN,
+            ModifierKind::FireRate(_) => LIME,
+            ModifierKind::BulletRange(_) => SKYBLUE,
+        };
+        draw_circle(draw_x, draw_y, config::TILE_SIZE * 0.5, color);
+    }
+}
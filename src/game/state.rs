@@ -1,6 +1,5 @@
 use ::rand::Rng;
 use ::rand::thread_rng;
-use macroquad::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
@@ -11,8 +10,10 @@ use crate::game::modifier::ModifierKind;
 use crate::map::Map;
 
 use super::bullet::Bullet;
+use super::event::GameEvent;
+use super::grid::SpatialGrid;
 use super::modifier::Modifier;
-use super::player::Player;
+use super::player::{Player, sanitize_name};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum Direction {
@@ -24,6 +25,10 @@ pub enum Direction {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GameStateDiff {
+    pub seq: u32,
+    /// Server simulation tick the diff was taken at.
+    pub tick: u64,
+    pub checksum: Option<u32>,
     pub players: HashMap<u32, Player>,
     pub removed_players: Vec<u32>,
     pub bullets: HashMap<u32, Bullet>,
@@ -35,6 +40,9 @@ pub struct GameStateDiff {
 impl GameStateDiff {
     fn new() -> Self {
         Self {
+            seq: 0,
+            tick: 0,
+            checksum: None,
             players: HashMap::new(),
             removed_players: Vec::new(),
             bullets: HashMap::new(),
@@ -60,6 +68,61 @@ impl PlayerPrevState {
             modifieres: HashMap::new(),
         }
     }
+
+    pub fn checksum(&self) -> u32 {
+        state_checksum(&self.players, &self.bullets, &self.modifieres)
+    }
+}
+
+/// The part of the map a client gets updates for: its player's position
+/// and the relevance half extent, in tiles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub half_w: f32,
+    pub half_h: f32,
+}
+
+impl Viewport {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        (x - self.x).abs() <= self.half_w && (y - self.y).abs() <= self.half_h
+    }
+
+    /// Ids in the grid cells overlapping the viewport.
+    pub fn query(&self, grid: &SpatialGrid) -> Vec<u32> {
+        grid.query_rect(self.x, self.y, self.half_w, self.half_h)
+    }
+}
+
+/// Order-independent checksum of the entities a client is expected to have after
+/// applying every diff so far. Players hidden with `to_render = false` are left out,
+/// since the client keeps them around while the server may drop them.
+fn state_checksum(
+    players: &HashMap<u32, Player>,
+    bullets: &HashMap<u32, Bullet>,
+    modifieres: &HashMap<u32, Modifier>,
+) -> u32 {
+    let mut checksum = 0u32;
+    for p in players.values().filter(|p| p.to_render) {
+        checksum = checksum.wrapping_add(fnv1a(&[0, p.id, p.x.to_bits(), p.y.to_bits(), p.health]));
+    }
+    for b in bullets.values() {
+        checksum = checksum.wrapping_add(fnv1a(&[1, b.id, b.x.to_bits(), b.y.to_bits()]));
+    }
+    for m in modifieres.values() {
+        checksum = checksum.wrapping_add(fnv1a(&[2, m.id, m.x.to_bits(), m.y.to_bits()]));
+    }
+    checksum
+}
+
+fn fnv1a(words: &[u32]) -> u32 {
+    let mut hash = 0x811c9dc5u32;
+    for byte in words.iter().flat_map(|word| word.to_le_bytes()) {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -73,6 +136,21 @@ pub struct GameState {
 
     #[serde(skip_serializing, skip_deserializing, default)]
     pub prev_states: HashMap<u32, Box<PlayerPrevState>>,
+
+    #[serde(skip_serializing, skip_deserializing, default)]
+    pub viewports: HashMap<u32, (f32, f32)>,
+
+    #[serde(skip_serializing, skip_deserializing, default)]
+    pub events: Vec<GameEvent>,
+
+    #[serde(skip_serializing, skip_deserializing, default)]
+    pub player_grid: SpatialGrid,
+
+    #[serde(skip_serializing, skip_deserializing, default)]
+    pub bullet_grid: SpatialGrid,
+
+    #[serde(skip_serializing, skip_deserializing, default)]
+    pub modifier_grid: SpatialGrid,
 }
 
 impl GameState {
@@ -83,6 +161,11 @@ impl GameState {
             modifieres: HashMap::new(),
             last_spawn_modifieres: Instant::now(),
             prev_states: HashMap::new(),
+            viewports: HashMap::new(),
+            events: Vec::new(),
+            player_grid: SpatialGrid::default(),
+            bullet_grid: SpatialGrid::default(),
+            modifier_grid: SpatialGrid::default(),
         }
     }
 
@@ -92,37 +175,68 @@ impl GameState {
 
     pub fn remove(&mut self, player_id: Option<&u32>) {
         if let Some(id) = player_id {
-            self.players.remove(&id);
+            self.players.remove(id);
+            self.player_grid.remove(*id);
+            self.viewports.remove(id);
+        }
+    }
+
+    pub fn set_viewport(&mut self, player_id: u32, half_w: f32, half_h: f32) {
+        if let Some(viewport) = Self::clamp_viewport(half_w, half_h) {
+            self.viewports.insert(player_id, viewport);
         }
     }
 
+    /// The relevance half extent the server uses for a reported viewport.
+    pub fn clamp_viewport(half_w: f32, half_h: f32) -> Option<(f32, f32)> {
+        if !half_w.is_finite() || !half_h.is_finite() {
+            return None;
+        }
+        Some((
+            half_w.clamp(1.0, config::MAX_VIEW_HALF_WIDTH),
+            half_h.clamp(1.0, config::MAX_VIEW_HALF_HEIGHT),
+        ))
+    }
+
     pub fn get_snapshot(&mut self, player_id: Option<&u32>) -> Self {
         if let Some(pid) = player_id {
-            if let Some((px, py, half_w, half_h, _)) = self.resolve_viewport(pid) {
+            if let Some(view) = self.resolve_viewport(pid) {
                 let mut player_prev = PlayerPrevState {
                     players: HashMap::new(),
                     bullets: HashMap::new(),
                     modifieres: HashMap::new(),
                 };
 
-                for (&id, p) in &self.players {
-                    if self.is_in_viewport(px, py, p.x, p.y, half_w, half_h) {
+                for id in view.query(&self.player_grid) {
+                    if let Some(p) = self.players.get(&id)
+                        && view.contains(p.x, p.y)
+                    {
                         player_prev.players.insert(id, p.clone());
                     }
                 }
-                for (&id, b) in &self.bullets {
-                    if self.is_in_viewport(px, py, b.x, b.y, half_w, half_h) {
+                for id in view.query(&self.bullet_grid) {
+                    if let Some(b) = self.bullets.get(&id)
+                        && view.contains(b.x, b.y)
+                    {
                         player_prev.bullets.insert(id, b.clone());
                     }
                 }
-                for (&id, m) in &self.modifieres {
-                    if self.is_in_viewport(px, py, m.x, m.y, half_w, half_h) {
+                for id in view.query(&self.modifier_grid) {
+                    if let Some(m) = self.modifieres.get(&id)
+                        && view.contains(m.x, m.y)
+                    {
                         player_prev.modifieres.insert(id, m.clone());
                     }
                 }
+                // Only send what the diffs will keep up to date, so the client's
+                // view matches `prev_states` and the checksums line up.
+                let mut snapshot = GameState::new();
+                snapshot.players = player_prev.players.clone();
+                snapshot.bullets = player_prev.bullets.clone();
+                snapshot.modifieres = player_prev.modifieres.clone();
                 self.prev_states.insert(*pid, Box::new(player_prev));
 
-                return self.clone();
+                return snapshot;
             }
         }
 
@@ -144,27 +258,48 @@ impl GameState {
             return self.full_snapshot();
         }
 
-        let Some((px, py, half_w, half_h, pid)) = self.resolve_viewport(player_id.unwrap()) else {
+        let pid = *player_id.unwrap();
+        let Some(view) = self.resolve_viewport(&pid) else {
             return diff;
         };
 
         let prev = self.prev_states.get(&pid);
 
-        self.collect_player_changes(&mut diff, pid, px, py, half_w, half_h, prev);
-        self.collect_bullet_changes(&mut diff, px, py, half_w, half_h, prev);
-        self.collect_modifier_changes(&mut diff, px, py, half_w, half_h, prev);
+        self.collect_player_changes(&mut diff, pid, view, prev);
+        self.collect_bullet_changes(&mut diff, view, prev);
+        self.collect_modifier_changes(&mut diff, view, prev);
 
-        let new_prev = self.build_new_prev_state(px, py, half_w, half_h, *player_id.unwrap());
+        let new_prev = self.build_new_prev_state(view, pid);
         self.prev_states.insert(pid, Box::new(new_prev));
 
         diff
     }
 
-    pub fn resolve_viewport(&self, pid: &u32) -> Option<(f32, f32, f32, f32, u32)> {
+    /// Events since the last call, in the order they happened.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn checksum(&self) -> u32 {
+        state_checksum(&self.players, &self.bullets, &self.modifieres)
+    }
+
+    pub fn relevant_checksum(&self, player_id: u32) -> Option<u32> {
+        self.prev_states.get(&player_id).map(|prev| prev.checksum())
+    }
+
+    pub fn resolve_viewport(&self, pid: &u32) -> Option<Viewport> {
         self.players.get(pid).map(|player| {
-            let half_w = 50.0;
-            let half_h = 50.0;
-            (player.x, player.y, half_w, half_h, *pid)
+            let (half_w, half_h) = self.viewports.get(pid).copied().unwrap_or((
+                config::DEFAULT_VIEW_HALF_EXTENT,
+                config::DEFAULT_VIEW_HALF_EXTENT,
+            ));
+            Viewport {
+                x: player.x,
+                y: player.y,
+                half_w,
+                half_h,
+            }
         })
     }
 
@@ -172,10 +307,7 @@ impl GameState {
         &self,
         diff: &mut GameStateDiff,
         pid: u32,
-        px: f32,
-        py: f32,
-        half_w: f32,
-        half_h: f32,
+        view: Viewport,
         prev: Option<&Box<PlayerPrevState>>,
     ) {
         if let Some(local_player) = self.players.get(&pid) {
@@ -185,16 +317,19 @@ impl GameState {
             }
         }
 
-        for (&id, player) in &self.players {
+        for id in view.query(&self.player_grid) {
             if id == pid {
                 continue;
             }
+            let Some(player) = self.players.get(&id) else {
+                continue;
+            };
             if !player.to_render {
                 diff.removed_players.push(id);
                 continue;
             }
 
-            if self.is_in_viewport(px, py, player.x, player.y, half_w, half_h) {
+            if view.contains(player.x, player.y) {
                 let changed = prev.map_or(true, |p| p.players.get(&id) != Some(player));
                 if changed {
                     diff.players.insert(id, player.clone());
@@ -211,7 +346,7 @@ impl GameState {
                 let still_exists = self
                     .players
                     .get(&id)
-                    .map(|p| self.is_in_viewport(px, py, p.x, p.y, half_w, half_h))
+                    .map(|p| view.contains(p.x, p.y))
                     .unwrap_or(false);
 
                 if !still_exists {
@@ -224,14 +359,14 @@ impl GameState {
     pub fn collect_bullet_changes(
         &self,
         diff: &mut GameStateDiff,
-        px: f32,
-        py: f32,
-        half_w: f32,
-        half_h: f32,
+        view: Viewport,
         prev: Option<&Box<PlayerPrevState>>,
     ) {
-        for (&id, bullet) in &self.bullets {
-            if self.is_in_viewport(px, py, bullet.x, bullet.y, half_w, half_h) {
+        for id in view.query(&self.bullet_grid) {
+            let Some(bullet) = self.bullets.get(&id) else {
+                continue;
+            };
+            if view.contains(bullet.x, bullet.y) {
                 let changed = prev.map_or(true, |p| p.bullets.get(&id) != Some(bullet));
                 if changed {
                     diff.bullets.insert(id, bullet.clone());
@@ -245,7 +380,7 @@ impl GameState {
                     || !self
                         .bullets
                         .get(&id)
-                        .map(|b| self.is_in_viewport(px, py, b.x, b.y, half_w, half_h))
+                        .map(|b| view.contains(b.x, b.y))
                         .unwrap_or(false);
 
                 if removed {
@@ -258,14 +393,14 @@ impl GameState {
     pub fn collect_modifier_changes(
         &self,
         diff: &mut GameStateDiff,
-        px: f32,
-        py: f32,
-        half_w: f32,
-        half_h: f32,
+        view: Viewport,
         prev: Option<&Box<PlayerPrevState>>,
     ) {
-        for (&id, modifier) in &self.modifieres {
-            if self.is_in_viewport(px, py, modifier.x, modifier.y, half_w, half_h) {
+        for id in view.query(&self.modifier_grid) {
+            let Some(modifier) = self.modifieres.get(&id) else {
+                continue;
+            };
+            if view.contains(modifier.x, modifier.y) {
                 let changed = prev.map_or(true, |p| p.modifieres.get(&id) != Some(modifier));
                 if changed {
                     diff.modifieres.insert(id, modifier.clone());
@@ -277,13 +412,9 @@ impl GameState {
             for (&id, _) in &prev_state.modifieres {
                 let removed = prev_state.modifieres.contains_key(&id)
                     && (!self.modifieres.contains_key(&id)
-                        || !self.is_in_viewport(
-                            px,
-                            py,
+                        || !view.contains(
                             self.modifieres.get(&id).map(|m| m.x).unwrap_or(0.0),
                             self.modifieres.get(&id).map(|m| m.y).unwrap_or(0.0),
-                            half_w,
-                            half_h,
                         ));
 
                 if removed {
@@ -293,28 +424,30 @@ impl GameState {
         }
     }
 
-    pub fn build_new_prev_state(
-        &self,
-        px: f32,
-        py: f32,
-        half_w: f32,
-        half_h: f32,
-        player_id: u32,
-    ) -> PlayerPrevState {
+    pub fn build_new_prev_state(&self, view: Viewport, player_id: u32) -> PlayerPrevState {
         let mut new_prev = PlayerPrevState::new();
 
-        for (&id, player) in &self.players {
-            if id == player_id || self.is_in_viewport(px, py, player.x, player.y, half_w, half_h) {
+        if let Some(player) = self.players.get(&player_id) {
+            new_prev.players.insert(player_id, player.clone());
+        }
+        for id in view.query(&self.player_grid) {
+            if let Some(player) = self.players.get(&id)
+                && view.contains(player.x, player.y)
+            {
                 new_prev.players.insert(id, player.clone());
             }
         }
-        for (&id, bullet) in &self.bullets {
-            if self.is_in_viewport(px, py, bullet.x, bullet.y, half_w, half_h) {
+        for id in view.query(&self.bullet_grid) {
+            if let Some(bullet) = self.bullets.get(&id)
+                && view.contains(bullet.x, bullet.y)
+            {
                 new_prev.bullets.insert(id, bullet.clone());
             }
         }
-        for (&id, modifier) in &self.modifieres {
-            if self.is_in_viewport(px, py, modifier.x, modifier.y, half_w, half_h) {
+        for id in view.query(&self.modifier_grid) {
+            if let Some(modifier) = self.modifieres.get(&id)
+                && view.contains(modifier.x, modifier.y)
+            {
                 new_prev.modifieres.insert(id, modifier.clone());
             }
         }
@@ -322,15 +455,45 @@ impl GameState {
         new_prev
     }
 
-    pub fn create_player(&mut self, map: &Map) -> Player {
+    pub fn create_player(&mut self, map: &Map, name: &str) -> Player {
         let id = self.next_id();
+        let name = self.unique_name(&sanitize_name(name));
         let (x, y) = map.generate_spawn_position(config::PLAYER_RADIUS);
-        let player = Player::new(id, x, y);
+        let player = Player::new(id, name, x, y);
         self.players.insert(id, player.clone());
+        self.player_grid.update(id, x, y);
 
         player
     }
 
+    /// Appends `-2`, `-3`, ... when the name is taken, ignoring case.
+    fn unique_name(&self, name: &str) -> String {
+        let taken = |candidate: &str| {
+            self.players
+                .values()
+                .any(|p| p.name.eq_ignore_ascii_case(candidate))
+        };
+        if !taken(name) {
+            return name.to_string();
+        }
+
+        (2..)
+            .map(|n| {
+                let suffix = format!("-{}", n);
+                let base_len = config::MAX_NICKNAME_LEN.saturating_sub(suffix.len());
+                format!("{}{}", &name[..name.len().min(base_len)], suffix)
+            })
+            .find(|candidate| !taken(candidate))
+            .unwrap()
+    }
+
+    /// Display name for `id`, or the bare id if the player is gone.
+    pub fn player_name(&self, id: u32) -> String {
+        self.players
+            .get(&id)
+            .map_or_else(|| format!("#{}", id), |p| p.name.clone())
+    }
+
     pub fn move_player(&mut self, player_id: Option<&u32>, dir: Direction, map: &Map) {
         if let Some(id) = player_id {
             if let Some(player) = self.players.get_mut(id) {
@@ -393,6 +556,7 @@ impl GameState {
                     hit_radius: player.hit_radius,
                 };
 
+                self.bullet_grid.update(bullet.id, bullet.x, bullet.y);
                 self.bullets.insert(bullet.id, bullet);
             }
         }
@@ -416,14 +580,34 @@ impl GameState {
                 to_remove.push(bullet.id);
                 continue;
             }
+            self.bullet_grid.update(bullet.id, bullet.x, bullet.y);
 
-            for (player_id, player) in self.players.iter_mut() {
-                if bullet.owner_id != *player_id && player.hit_by(bullet) {
+            let reach = config::PLAYER_RADIUS + bullet.hit_radius;
+            for player_id in self.player_grid.query_radius(bullet.x, bullet.y, reach) {
+                if bullet.owner_id == player_id {
+                    continue;
+                }
+                let Some(player) = self.players.get_mut(&player_id) else {
+                    continue;
+                };
+                let health = player.health;
+                if player.hit_by(bullet) {
                     to_remove.push(bullet.id);
+                    self.events.push(GameEvent::Damage {
+                        attacker: bullet.owner_id,
+                        victim: player_id,
+                        amount: health - player.health,
+                        x: player.x,
+                        y: player.y,
+                    });
 
                     if player.health == 0 {
                         player.to_render = false;
-                        to_respawn.push(*player_id);
+                        to_respawn.push(player_id);
+                        self.events.push(GameEvent::Kill {
+                            killer: bullet.owner_id,
+                            victim: player_id,
+                        });
                         if let Some(owner) = self.players.get_mut(&bullet.owner_id) {
                             owner.kills += 1;
                         }
@@ -436,6 +620,7 @@ impl GameState {
 
         for id in to_remove {
             self.bullets.remove(&id);
+            self.bullet_grid.remove(id);
         }
 
         for player_id in to_respawn {
@@ -477,14 +662,27 @@ impl GameState {
                 player.is_moving = false;
                 player.move_target = None;
             }
+            self.player_grid.update(player.id, player.x, player.y);
 
-            for (id, modifier) in &self.modifieres {
+            let nearby =
+                self.modifier_grid
+                    .query_radius(player.x, player.y, config::MODIFIER_PICKUP_RADIUS);
+            for id in nearby {
+                let Some(modifier) = self.modifieres.get(&id) else {
+                    continue;
+                };
                 let dx = modifier.x - player.x;
                 let dy = modifier.y - player.y;
                 let dist = (dx * dx + dy * dy).sqrt();
 
-                if dist < 1.0 {
-                    picked_modifiers.push(*id);
+                if dist < config::MODIFIER_PICKUP_RADIUS {
+                    picked_modifiers.push(id);
+                    self.events.push(GameEvent::Pickup {
+                        player: player.id,
+                        kind: modifier.kind.clone(),
+                        x: modifier.x,
+                        y: modifier.y,
+                    });
                     match modifier.kind {
                         ModifierKind::Heal(health) => {
                             player.health += health;
@@ -508,6 +706,7 @@ impl GameState {
         }
         for id in picked_modifiers {
             self.modifieres.remove(&id);
+            self.modifier_grid.remove(id);
         }
     }
 
@@ -523,6 +722,7 @@ impl GameState {
         }
         self.last_spawn_modifieres = Instant::now();
         self.modifieres.clear();
+        self.modifier_grid.clear();
         let mut rng = thread_rng();
         let modifiers_count = ((map.width * map.height) as f32 / 500.0).round() as u32;
 
@@ -544,6 +744,7 @@ impl GameState {
                 kind: ModifierKind::random(&mut rng),
             };
 
+            self.modifier_grid.update(id, x, y);
             self.modifieres.insert(id, modifier);
         }
     }
@@ -553,133 +754,57 @@ impl GameState {
             let (x, y) = map.generate_spawn_position(player.radius);
             player.x = x;
             player.y = y;
+            self.player_grid.update(player_id, x, y);
             player.health = player.max_health;
             player.direction = Direction::Up;
             player.last_shot = Instant::now() - Duration::from_secs(5);
             player.deths += 1;
             player.to_render = true;
+            self.events.push(GameEvent::Respawn {
+                player: player_id,
+                x,
+                y,
+            });
         }
     }
+}
 
-    pub fn render(&self, current_id: Option<u32>, player_pos: (f32, f32)) {
-        let offset_x = screen_width() / 2.0 - player_pos.0 * config::TILE_SIZE;
-        let offset_y = screen_height() / 2.0 - player_pos.1 * config::TILE_SIZE;
-
-        for player in self.players.values() {
-            if Some(player.id) == current_id {
-                player.render(current_id, offset_x, offset_y);
-                continue;
-            }
-
-            let dx = player.x - player_pos.0;
-            let dy = player.y - player_pos.1;
-
-            let screen_x = screen_width() / 2.0 + dx * config::TILE_SIZE;
-            let screen_y = screen_height() / 2.0 + dy * config::TILE_SIZE;
-
-            if screen_x < 0.0
-                || screen_x > screen_width()
-                || screen_y < 0.0
-                || screen_y > screen_height()
-            {
-                self.draw_offscreen_arrow(dx, dy);
-            } else {
-                player.render(current_id, offset_x, offset_y);
-            }
-        }
-
-        for bullet in self.bullets.values() {
-            bullet.render(offset_x, offset_y);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for modifier in self.modifieres.values() {
-            modifier.render(offset_x, offset_y);
+    fn with_players(names: &[&str]) -> GameState {
+        let mut state = GameState::new();
+        for (id, name) in names.iter().enumerate() {
+            let player = Player::new(id as u32, name.to_string(), 0.0, 0.0);
+            state.players.insert(player.id, player);
         }
-
-        self.render_hud(current_id);
+        state
     }
 
-    pub fn draw_offscreen_arrow(&self, dx: f32, dy: f32) {
-        let angle = dy.atan2(dx);
-        let margin = 20.0;
-        let half_w = screen_width() / 2.0 - margin;
-        let half_h = screen_height() / 2.0 - margin;
-
-        let mut arrow_x = screen_width() / 2.0 + half_w * angle.cos();
-        let mut arrow_y = screen_height() / 2.0 + half_h * angle.sin();
-
-        arrow_x = arrow_x.clamp(margin, screen_width() - margin);
-        arrow_y = arrow_y.clamp(margin, screen_height() - margin);
-
-        let arrow_size = 10.0;
-        let angle_offset = std::f32::consts::PI / 6.0;
-
-        let tip = Vec2::new(arrow_x, arrow_y);
-        let left = Vec2::new(
-            arrow_x - arrow_size * (angle - angle_offset).cos(),
-            arrow_y - arrow_size * (angle - angle_offset).sin(),
-        );
-        let right = Vec2::new(
-            arrow_x - arrow_size * (angle + angle_offset).cos(),
-            arrow_y - arrow_size * (angle + angle_offset).sin(),
-        );
+    #[test]
+    fn unique_name_keeps_free_names() {
+        let state = with_players(&["alice"]);
+        assert_eq!(state.unique_name("bob"), "bob");
+    }
 
-        draw_triangle(tip, left, right, RED);
+    #[test]
+    fn unique_name_counts_up_ignoring_case() {
+        let state = with_players(&["bob", "BOB-2"]);
+        assert_eq!(state.unique_name("Bob"), "Bob-3");
     }
 
-    pub fn render_hud(&self, current_id: Option<u32>) {
-        let mut players: Vec<_> = self.players.values().collect();
-        players.sort_by_key(|p| p.id);
+    #[test]
+    fn unique_name_suffix_stays_within_the_cap() {
+        let full = "x".repeat(config::MAX_NICKNAME_LEN);
+        let second = format!("{}-2", &full[..config::MAX_NICKNAME_LEN - 2]);
+        let state = with_players(&[&full, &second]);
 
-        let mut y = 10.0;
-        draw_text(
-            &format!("Players online: {}", players.len()),
-            10.0,
-            y,
-            20.0,
-            WHITE,
+        let third = state.unique_name(&full);
+        assert_eq!(
+            third,
+            format!("{}-3", &full[..config::MAX_NICKNAME_LEN - 2])
         );
-        y += 25.0;
-
-        for player in players {
-            let current_marker = if Some(player.id) == current_id {
-                "(You)"
-            } else {
-                "     "
-            };
-            draw_text(
-                &format!(
-                    "ID: {}{} | Kills: {} | Deths: {} | Health {}/{} | speed {} | Damage: {}| fire rate {}",
-                    player.id,
-                    current_marker,
-                    player.kills,
-                    player.deths,
-                    player.health,
-                    player.max_health,
-                    player.walk_speed,
-                    player.bullet_damage,
-                    player.fire_rate,
-                ),
-                10.0,
-                y,
-                20.0,
-                WHITE,
-            );
-            y += 25.0;
-        }
-    }
-
-    pub fn is_in_viewport(
-        &self,
-        px: f32,
-        py: f32,
-        ox: f32,
-        oy: f32,
-        half_w: f32,
-        half_h: f32,
-    ) -> bool {
-        let dx = (ox - px).abs();
-        let dy = (oy - py).abs();
-        dx <= half_w && dy <= half_h
+        assert_eq!(third.len(), config::MAX_NICKNAME_LEN);
     }
 }
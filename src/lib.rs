@@ -3,6 +3,11 @@ pub mod config;
 pub mod game;
 pub mod map;
 pub mod network;
+pub mod registry;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "client")]
 pub mod ui;
 pub mod utils;
@@ -1,109 +1,137 @@
 use macroquad::prelude::*;
 use std::env;
-use std::sync::atomic::AtomicBool;
-use std::sync::atomic::Ordering;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
 use std::time::Duration;
 use std::time::Instant;
-use std::{
-    net::{SocketAddr, UdpSocket},
-    sync::mpsc::{self, Receiver, Sender},
-    sync::{Arc, Mutex},
-    thread,
+use termarena::client::bindings::Bindings;
+use termarena::client::connection::Connection;
+use termarena::client::key_event_handler::{
+    listen_bindings, listen_chat, listen_debug, listen_move, listen_quit, listen_scoreboard,
+    listen_shoot,
 };
-use termarena::client::key_event_handler::{listen_move, listen_quit, listen_shoot};
-use termarena::client::state::ClientState;
+use termarena::client::profile::{Profile, RecentServer};
+use termarena::client::reconnect::Reconnector;
 use termarena::config;
 use termarena::map::Map;
-use termarena::network::recv_message;
-use termarena::network::state::ServerMessage;
-use termarena::network::state::ServerMessageType;
-use termarena::network::{send_message, state::ClientMessage, state::MapDownloader};
-use termarena::ui::loading;
+use termarena::network::discovery::discover_servers;
+use termarena::network::registry::list_servers;
+use termarena::network::state::ClientMessage;
+use termarena::ui::bindings::BindingsScreen;
+use termarena::ui::chat::{ChatAction, ChatBox};
+use termarena::ui::debug::{self, DebugMode};
+use termarena::ui::menu::{Menu, MenuAction};
+use termarena::ui::{chat, closed, feed, loading, scoreboard, status};
+use termarena::utils;
+
+enum GameEnd {
+    Quit,
+    Closed(String),
+    Disconnected(String),
+}
 
 #[macroquad::main("Client")]
 async fn main() {
     let args: Vec<String> = env::args().collect();
-    let server_addr_str = args
-        .get(1)
-        .cloned()
-        .unwrap_or_else(|| format!("127.0.0.1:{}", config::UDP_PORT));
-    let server_addr: SocketAddr = server_addr_str.parse().unwrap();
-    let (tx, rx): (Sender<ClientMessage>, Receiver<ClientMessage>) = mpsc::channel();
-    let socket = UdpSocket::bind("0.0.0.0:0").expect("Failed to bind client socket");
-    let client_state = Arc::new(Mutex::new(ClientState::new()));
-    let map: Arc<Mutex<Option<Arc<Map>>>> = Arc::new(Mutex::new(None));
-    let map_downloader = Arc::new(Mutex::new(MapDownloader::new()));
-    let map_downloader_recv = Arc::clone(&map_downloader);
-    let map_downloader_send = Arc::clone(&map_downloader);
-    let map_loaded = Arc::new(AtomicBool::new(false));
-    let map_loaded_clone = Arc::clone(&map_loaded);
-    let mut texture_inited = false;
+    let mut profile = Profile::load();
+    let mut bindings = Bindings::load();
+    let mut menu = Menu::new(&profile);
 
-    socket
-        .set_nonblocking(false)
-        .expect("Failed to set nonblocking");
-    send_message(&socket, &ClientMessage::Init, server_addr);
-
-    let socket_clone = socket.try_clone().unwrap();
-    let map_clone_check = Arc::clone(&map);
-    thread::spawn(move || {
-        loop {
-            let map_ready = {
-                let map = map_clone_check.lock().unwrap();
-                map.is_some()
-            };
-            if map_ready {
-                break;
-            }
+    let initial_server = match args.get(1).map(String::as_str) {
+        Some("--lan") => {
+            let index = args.get(2).and_then(|i| i.parse().ok()).unwrap_or(0);
+            Some(choose_lan_server(index))
+        }
+        Some("--registry") => {
+            let registry_addr = args.get(2).map(String::as_str).unwrap_or("127.0.0.1");
+            let index = args.get(3).and_then(|i| i.parse().ok()).unwrap_or(0);
+            Some(choose_registry_server(registry_addr, index))
+        }
+        Some(address) => {
+            let tcp_port = args
+                .get(2)
+                .and_then(|port| port.parse().ok())
+                .unwrap_or(config::TCP_PORT as u16);
+            Some(Ok((address.to_string(), tcp_port)))
+        }
+        None => None,
+    };
+    match initial_server {
+        Some(Ok((address, tcp_port))) => menu.set_server(&address, tcp_port),
+        Some(Err(e)) => menu.set_error(e),
+        None => {}
+    }
+
+    while let Some((connection, server, nickname)) = run_menu(&mut menu).await {
+        profile.nickname = nickname;
+        profile.remember_server(server);
+        profile.save();
+        menu.set_recent(&profile.recent_servers);
 
-            let map_chunk_ids = { map_downloader_send.lock().unwrap().get_exist_chunk_id() };
-
-            send_message(
-                &socket_clone,
-                &ClientMessage::Map(map_chunk_ids),
-                server_addr,
-            );
-            thread::sleep(Duration::from_secs(3));
-        }
-    });
-
-    let socket_clone_recv = socket.try_clone().unwrap();
-    socket_clone_recv
-        .set_nonblocking(false)
-        .expect("Failed to set blocking mode");
-    let client_state_clone = Arc::clone(&client_state);
-    thread::spawn(move || {
-        loop {
-            if let Some((msg, _addr)) = recv_message::<ServerMessage>(&socket_clone_recv) {
-                let mut clinet_state_clone_lock = client_state_clone.lock().unwrap();
-                match msg {
-                    ServerMessage { src: _, message } => match message {
-                        ServerMessageType::InitPlayer(player) => {
-                            clinet_state_clone_lock.init_player(player);
-                        }
-                        ServerMessageType::Map(chunk) => {
-                            let mut map_downloader_lock = map_downloader_recv.lock().unwrap();
-                            map_loaded_clone
-                                .store(map_downloader_lock.load_chunk(chunk), Ordering::Relaxed);
-                        }
-                        ServerMessageType::GameState(state) => {
-                            clinet_state_clone_lock.update_state(state);
-                        }
-                        ServerMessageType::GameStateDiff(state_diff) => {
-                            clinet_state_clone_lock.update_state_diff(state_diff);
-                        }
-                    },
+        match play(connection, &mut bindings).await {
+            GameEnd::Quit => {}
+            GameEnd::Closed(reason) => menu.set_error(format!("Server closed: {}", reason)),
+            GameEnd::Disconnected(reason) => menu.set_error(format!("Disconnected: {}", reason)),
+        }
+    }
+}
+
+/// Shows the menu until a connection is made or the player quits.
+async fn run_menu(menu: &mut Menu) -> Option<(Connection, RecentServer, String)> {
+    let mut pending: Option<(Receiver<io::Result<Connection>>, RecentServer, String)> = None;
+
+    loop {
+        if let Some((rx, server, nickname)) = pending.take() {
+            match rx.try_recv() {
+                Ok(Ok(connection)) => {
+                    menu.set_connecting(None);
+                    return Some((connection, server, nickname));
                 }
+                Ok(Err(e)) => menu.set_error(format!("Failed to connect: {}", e)),
+                Err(TryRecvError::Empty) => pending = Some((rx, server, nickname)),
+                Err(TryRecvError::Disconnected) => menu.set_error("Failed to connect"),
             }
         }
-    });
 
-    let socket_clone_send = socket.try_clone().unwrap();
-    thread::spawn(move || {
-        while let Ok(msg) = rx.recv() {
-            let _ = send_message(&socket_clone_send, &msg, server_addr);
+        match menu.update() {
+            Some(MenuAction::Quit) => return None,
+            Some(MenuAction::Connect {
+                address,
+                tcp_port,
+                nickname,
+            }) => match utils::resolve_addr(&address, config::UDP_PORT as u16) {
+                Ok(server_addr) => {
+                    let tcp_addr = SocketAddr::new(server_addr.ip(), tcp_port);
+                    let (tx, rx) = mpsc::channel();
+                    let name = nickname.clone();
+                    thread::spawn(move || {
+                        let _ = tx.send(Connection::connect(server_addr, tcp_addr, &name));
+                    });
+                    menu.set_connecting(Some(server_addr.to_string()));
+                    let server = RecentServer { address, tcp_port };
+                    pending = Some((rx, server, nickname));
+                }
+                Err(e) => menu.set_error(e),
+            },
+            None => {}
         }
-    });
+
+        menu.draw();
+        next_frame().await;
+    }
+}
+
+async fn play(mut connection: Connection, bindings: &mut Bindings) -> GameEnd {
+    let mut bindings_screen: Option<BindingsScreen> = None;
+    let mut chat_box: Option<ChatBox> = None;
+    let mut debug_mode = DebugMode::Off;
+    let mut map: Option<Arc<Map>> = None;
+    let mut texture_inited = false;
+    let mut reconnector = Reconnector::new();
+    let mut lost: Option<String> = None;
 
     let mut last_update = Instant::now();
     let mut loading_frame = 0;
@@ -113,56 +141,207 @@ async fn main() {
     loop {
         clear_background(BLACK);
 
-        if map_loaded.load(Ordering::Relaxed) {
-            let map_downloader_lock = map_downloader.lock().unwrap();
-            if map.lock().unwrap().is_none()
-                && let Some(new_map) = map_downloader_lock.try_build_map()
+        let server_closed = connection.state().server_closed.clone();
+        if let Some(reason) = server_closed {
+            closed::draw_closed_screen("Server closed", &reason);
+            if listen_quit(bindings) || is_key_pressed(KeyCode::Escape) {
+                return GameEnd::Closed(reason);
+            }
+            next_frame().await;
+            continue;
+        }
+        if let Some(reason) = &lost {
+            closed::draw_closed_screen("Disconnected", reason);
+            if listen_quit(bindings) || is_key_pressed(KeyCode::Escape) {
+                return GameEnd::Disconnected(reason.clone());
+            }
+            next_frame().await;
+            continue;
+        }
+
+        if connection.map_loaded() {
+            if map.is_none()
+                && let Some(new_map) = connection.build_map()
             {
                 if !texture_inited {
                     texture_inited = new_map.init_texture();
                 }
-                *map.lock().unwrap() = Some(Arc::new(new_map));
+                map = Some(Arc::new(new_map));
             }
         } else {
             thread::sleep(Duration::from_millis(50));
         }
 
-        if let Some(direction) = listen_move() {
-            let _ = tx.send(ClientMessage::Move(direction));
+        if let Some(screen) = bindings_screen.as_mut() {
+            if !screen.update(bindings) {
+                bindings_screen = None;
+            }
+        } else if let Some(chat_box_open) = chat_box.as_mut() {
+            match chat_box_open.update() {
+                Some(ChatAction::Send(text)) => {
+                    connection.send_chat(&text);
+                    chat_box = None;
+                }
+                Some(ChatAction::Close) => chat_box = None,
+                None => {}
+            }
+        } else {
+            if let Some(direction) = listen_move(bindings) {
+                connection.send_move(direction);
+            }
+            if listen_shoot(bindings) {
+                connection.shoot();
+            }
+            if listen_bindings(bindings) {
+                bindings_screen = Some(BindingsScreen::new());
+            }
+            if listen_chat(bindings) {
+                chat_box = Some(ChatBox::new());
+            }
+            if listen_debug(bindings) {
+                debug_mode = debug_mode.next();
+            }
+            if listen_quit(bindings) {
+                connection.disconnect();
+                return GameEnd::Quit;
+            }
+        }
+
+        let (half_w, half_h) = visible_half_extent();
+        connection.set_viewport(half_w, half_h);
+        connection.poll();
+
+        if let Some(new_connection) = reconnector.update(&connection) {
+            // The old socket is no longer bound, so it is dropped without `Quit`.
+            drop(std::mem::replace(&mut connection, new_connection));
+            map = None;
+            texture_inited = false;
         }
-        if listen_shoot() {
-            let _ = tx.send(ClientMessage::Shoot);
+        if reconnector.gave_up() {
+            let silence = connection.state().silence();
+            lost = Some(format!(
+                "No response from the server for {}s",
+                silence.as_secs()
+            ));
         }
-        if listen_quit() {
-            let _ = tx.send(ClientMessage::Quit);
-            break;
+
+        if let Some(reason) = connection.loading_failure() {
+            connection.send(ClientMessage::Quit);
+            lost = Some(reason);
+            continue;
         }
 
-        let locked_client = client_state.lock().unwrap();
-        let map_ready = map.lock().unwrap().is_some();
+        let locked_client = connection.state();
         let gs_ready = locked_client.game_state.is_some();
         let player_ready = locked_client.get_current_player().is_some();
 
-        if map_ready && gs_ready && player_ready {
+        if let Some(map_arc) = map.as_ref()
+            && gs_ready
+            && player_ready
+        {
             let player = locked_client.get_current_player().unwrap().clone();
             let gs_arc = Arc::clone(locked_client.game_state.as_ref().unwrap());
             let current_id = locked_client.id;
+            let kill_feed = locked_client.kill_feed();
+            let floating_texts = locked_client.floating_texts();
+            let chat_log = locked_client.chat_log();
+            let (ping_ms, packet_loss) = (locked_client.ping_ms(), locked_client.packet_loss());
             drop(locked_client);
+            let viewport = connection.relevance_viewport();
 
-            if let Some(map_arc) = map.lock().unwrap().as_ref() {
-                map_arc.render((player.x, player.y));
-            }
-
+            map_arc.render((player.x, player.y));
             gs_arc.render(current_id, (player.x, player.y));
+            if debug_mode == DebugMode::World {
+                debug::draw_debug_world(&gs_arc, (player.x, player.y), viewport);
+            }
+            feed::draw_floating_texts(&floating_texts, (player.x, player.y));
+            feed::draw_kill_feed(&kill_feed, Some(&player.name));
+            chat::draw_chat_log(&chat_log, chat_box.is_some());
+            status::draw_net_indicator(ping_ms, packet_loss);
+            if debug_mode != DebugMode::Off {
+                debug::draw_debug_panel(&connection.state(), &player, viewport);
+            }
         } else {
+            drop(locked_client);
             if last_update.elapsed() > std::time::Duration::from_millis(300) {
                 loading_frame += 1;
                 last_update = std::time::Instant::now();
             }
 
-            loading::draw_loading_screen(loading_frame, &map_downloader);
+            loading::draw_loading_screen(loading_frame, connection.map_downloader());
+        }
+
+        if let Some(screen) = &bindings_screen {
+            screen.draw(bindings);
+        } else if let Some(chat_box) = &chat_box {
+            chat_box.draw();
+        } else if listen_scoreboard(bindings) {
+            let locked_client = connection.state();
+            let entries = locked_client.scoreboard();
+            let current_id = locked_client.id;
+            drop(locked_client);
+            scoreboard::draw_scoreboard(&entries, current_id);
+        }
+
+        if gs_ready && connection.is_interrupted() {
+            status::draw_interrupted(connection.state().silence(), reconnector.attempts());
         }
 
         next_frame().await;
     }
 }
+
+fn visible_half_extent() -> (f32, f32) {
+    (
+        screen_width() / 2.0 / config::TILE_SIZE + config::VIEW_MARGIN,
+        screen_height() / 2.0 / config::TILE_SIZE + config::VIEW_MARGIN,
+    )
+}
+
+fn choose_lan_server(index: usize) -> Result<(String, u16), String> {
+    let servers = discover_servers(config::DISCOVERY_TIMEOUT);
+    if servers.is_empty() {
+        return Err("No servers found on the local network".to_string());
+    }
+
+    for (i, server) in servers.iter().enumerate() {
+        println!(
+            "[{}] {} {} | map {}x{} | players {}",
+            i,
+            server.info.name,
+            server.addr,
+            server.info.map_width,
+            server.info.map_height,
+            server.info.players,
+        );
+    }
+
+    servers
+        .into_iter()
+        .nth(index)
+        .map(|server| (server.addr.to_string(), server.info.tcp_port))
+        .ok_or_else(|| format!("No server with index {}", index))
+}
+
+fn choose_registry_server(registry_addr: &str, index: usize) -> Result<(String, u16), String> {
+    let registry_addr = utils::resolve_addr(registry_addr, config::REGISTRY_PORT as u16)?;
+    let Some(servers) = list_servers(registry_addr) else {
+        return Err(format!("No response from registry {}", registry_addr));
+    };
+    if servers.is_empty() {
+        return Err(format!("Registry {} has no servers", registry_addr));
+    }
+
+    for (i, server) in servers.iter().enumerate() {
+        println!(
+            "[{}] {} {} | {} | players {}",
+            i, server.name, server.addr, server.mode, server.players,
+        );
+    }
+
+    servers
+        .into_iter()
+        .nth(index)
+        .map(|server| (server.addr.to_string(), server.tcp_port))
+        .ok_or_else(|| format!("No server with index {}", index))
+}
@@ -0,0 +1,45 @@
+use std::env;
+use termarena::config;
+use termarena::network::registry::list_servers;
+use termarena::registry;
+use termarena::utils;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("list") {
+        run_list(args.get(2));
+        return;
+    }
+
+    let port = args
+        .get(1)
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(config::REGISTRY_PORT as u16);
+
+    registry::run_registry(port);
+}
+
+fn run_list(addr: Option<&String>) {
+    let addr_str = addr.map(String::as_str).unwrap_or("127.0.0.1");
+    let addr = match utils::resolve_addr(addr_str, config::REGISTRY_PORT as u16) {
+        Ok(addr) => addr,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("Usage: registry list [host:port]");
+            return;
+        }
+    };
+
+    let Some(servers) = list_servers(addr) else {
+        eprintln!("No response from registry {}", addr);
+        return;
+    };
+
+    println!("Servers: {}", servers.len());
+    for server in servers {
+        println!(
+            "  {} | {} | {} | players {}",
+            server.addr, server.name, server.mode, server.players
+        );
+    }
+}
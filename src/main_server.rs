@@ -1,20 +1,161 @@
 use std::env;
+use std::net::{IpAddr, SocketAddr};
+use termarena::client::{query, tcp};
 use termarena::config;
-use termarena::server;
+use termarena::network::state::AdminCommand;
+use termarena::server::{ServerBuilder, ServerConfig};
 use termarena::utils;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let port = args
+    if args.get(1).map(String::as_str) == Some("admin") {
+        run_admin(&args[2..]);
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("query") {
+        run_query(&args[2..]);
+        return;
+    }
+
+    let usage = "Usage: server [--bind <ip>]... [--tick-rate <hz>] [--snapshot-rate <hz>] \
+                 [--no-compression] [udp_port] [tcp_port] [name] [registry]";
+    let mut bind = Vec::new();
+    let mut tick_rate = config::TICK_RATE;
+    let mut snapshot_rate = config::SNAPSHOT_RATE;
+    let mut compression = true;
+    let mut positional = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        let parsed = match arg.as_str() {
+            "--bind" => iter
+                .next()
+                .and_then(|ip| ip.trim_matches(['[', ']']).parse::<IpAddr>().ok())
+                .map(|ip| bind.push(ip)),
+            "--tick-rate" => iter
+                .next()
+                .and_then(|hz| hz.parse().ok())
+                .map(|hz| tick_rate = hz),
+            "--snapshot-rate" => iter
+                .next()
+                .and_then(|hz| hz.parse().ok())
+                .map(|hz| snapshot_rate = hz),
+            "--no-compression" => {
+                compression = false;
+                Some(())
+            }
+            _ => {
+                positional.push(arg.clone());
+                Some(())
+            }
+        };
+        if parsed.is_none() {
+            eprintln!("{}", usage);
+            return;
+        }
+    }
+
+    let port = positional
+        .first()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(config::UDP_PORT as u16);
+    let tcp_port = positional
         .get(1)
-        .cloned()
-        .unwrap_or_else(|| config::UDP_PORT.to_string());
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(config::TCP_PORT as u16);
+
+    let mut server_config = ServerConfig::new(port, tcp_port);
+    server_config.bind = bind;
+    server_config.tick_rate = tick_rate;
+    server_config.snapshot_rate = snapshot_rate;
+    server_config.compression = compression;
+    if let Some(name) = positional.get(2) {
+        server_config.name = name.clone();
+    }
+    if let Some(registry) = positional.get(3) {
+        match utils::resolve_addr(registry, config::REGISTRY_PORT as u16) {
+            Ok(addr) => server_config.registry = Some(addr),
+            Err(e) => {
+                eprintln!("Invalid registry address: {}", e);
+                return;
+            }
+        }
+    }
+
+    for ip in utils::local_addresses() {
+        println!("Local address: {}", SocketAddr::new(ip, port));
+    }
+
+    let server = match ServerBuilder::with_config(server_config).start() {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Could not start server: {:?}", e);
+            return;
+        }
+    };
+
+    let stopper = server.stopper();
+    if let Err(e) = ctrlc::set_handler(move || stopper.stop("Server is shutting down")) {
+        eprintln!("Could not install Ctrl-C handler: {:?}", e);
+    }
 
-    let local_ip = utils::get_local_ip().unwrap_or("unknown".to_string());
+    server.wait();
+}
+
+fn run_admin(args: &[String]) {
+    let usage = "Usage: server admin <host:tcp_port> players | kick <id>";
+    let Some(addr) = args
+        .first()
+        .and_then(|a| utils::resolve_addr(a, config::TCP_PORT as u16).ok())
+    else {
+        eprintln!("{}", usage);
+        return;
+    };
+
+    let command = match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("players"), None) => AdminCommand::Players,
+        (Some("kick"), Some(id)) => match id.parse() {
+            Ok(id) => AdminCommand::Kick(id),
+            Err(_) => {
+                eprintln!("{}", usage);
+                return;
+            }
+        },
+        _ => {
+            eprintln!("{}", usage);
+            return;
+        }
+    };
+
+    match tcp::admin(addr, command) {
+        Some(response) => println!("{}", response),
+        None => eprintln!("No response from {}", addr),
+    }
+}
 
-    let local_with_port = format!("{}:{}", local_ip, port);
+fn run_query(args: &[String]) {
+    let Some(addr) = args
+        .first()
+        .and_then(|a| utils::resolve_addr(a, config::UDP_PORT as u16).ok())
+    else {
+        eprintln!("Usage: server query <host:udp_port>");
+        return;
+    };
 
-    println!("Local IP: {}", local_with_port);
+    let Some(info) = query::query(addr) else {
+        eprintln!("No response from {}", addr);
+        return;
+    };
 
-    server::run_server(port);
+    println!("Name:    {}", info.name);
+    println!("Mode:    {}", info.mode);
+    println!("Map:     {}x{}", info.map_width, info.map_height);
+    println!("Uptime:  {}s", info.uptime_secs);
+    println!("Desyncs: {}", info.desyncs);
+    println!("Players: {}", info.players.len());
+    for player in info.players {
+        println!(
+            "  ID: {} | Name: {} | Kills: {} | Deths: {}",
+            player.id, player.name, player.kills, player.deths
+        );
+    }
 }
@@ -0,0 +1,185 @@
+use std::env;
+use std::io;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use termarena::client::connection::Connection;
+use termarena::client::reconnect::Reconnector;
+use termarena::config;
+use termarena::map::Map;
+use termarena::network::state::ClientMessage;
+use termarena::tui::input::Input;
+use termarena::tui::render::{self, visible_half_extent};
+use termarena::tui::screen::Screen;
+use termarena::tui::terminal::Terminal;
+use termarena::utils;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let server_addr_str = args.get(1).map(String::as_str).unwrap_or("127.0.0.1");
+    let server_addr = match utils::resolve_addr(server_addr_str, config::UDP_PORT as u16) {
+        Ok(addr) => addr,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let tcp_port = args
+        .get(2)
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(config::TCP_PORT as u16);
+    let tcp_addr = SocketAddr::new(server_addr.ip(), tcp_port);
+    let name = args.get(3).map(String::as_str).unwrap_or("");
+
+    let connection = match Connection::connect(server_addr, tcp_addr, name) {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Failed to connect to {}: {:?}", server_addr, e);
+            return;
+        }
+    };
+
+    let terminal = match Terminal::enter() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            eprintln!("Failed to set up the terminal: {:?}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = run(&terminal, connection) {
+        drop(terminal);
+        eprintln!("Terminal error: {:?}", e);
+    }
+}
+
+fn run(terminal: &Terminal, mut connection: Connection) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    let (width, height) = terminal.size();
+    let mut screen = Screen::new(width, height);
+    let mut input = Input::new(terminal.release_events());
+    let mut map: Option<Map> = None;
+    let mut reconnector = Reconnector::new();
+    let mut lost: Option<String> = None;
+
+    let mut last_update = Instant::now();
+    let mut fps = 0;
+    let mut frames = 0;
+    let mut fps_since = Instant::now();
+    let mut loading_frame = 0;
+
+    loop {
+        let frame_start = Instant::now();
+        frames += 1;
+        if fps_since.elapsed() >= Duration::from_secs(1) {
+            fps = frames;
+            frames = 0;
+            fps_since = Instant::now();
+        }
+        let (width, height) = terminal.size();
+        screen.clear(width, height);
+        if input.take_resized() {
+            screen.invalidate();
+        }
+
+        let server_closed = connection.state().server_closed.clone();
+        let ended = server_closed
+            .map(|reason| ("Server closed", reason))
+            .or_else(|| lost.clone().map(|reason| ("Disconnected", reason)));
+        if let Some((title, reason)) = ended {
+            render::draw_closed(&mut screen, title, &reason);
+            screen.flush(&mut stdout)?;
+            input.read(config::TUI_FRAME_INTERVAL)?;
+            if input.dismiss() {
+                return Ok(());
+            }
+            continue;
+        }
+
+        if map.is_none() {
+            map = connection.build_map();
+        }
+
+        if let Some(direction) = input.movement() {
+            connection.send_move(direction);
+        }
+        if input.shoot() {
+            connection.shoot();
+        }
+        if let Some(text) = input.take_chat_sent() {
+            connection.send_chat(&text);
+        }
+        if input.quit() {
+            connection.disconnect();
+            return Ok(());
+        }
+
+        let (half_w, half_h) = visible_half_extent(width, height);
+        connection.set_viewport(half_w, half_h);
+        connection.poll();
+
+        if let Some(new_connection) = reconnector.update(&connection) {
+            // The old socket is no longer bound, so it is dropped without `Quit`.
+            drop(std::mem::replace(&mut connection, new_connection));
+            map = None;
+        }
+        if reconnector.gave_up() {
+            let silence = connection.state().silence();
+            lost = Some(format!(
+                "No response from the server for {}s",
+                silence.as_secs()
+            ));
+        }
+
+        let locked_client = connection.state();
+        let player = locked_client.get_current_player();
+        let game_state = locked_client.game_state.clone();
+        let kill_feed = locked_client.kill_feed();
+        let floating_texts = locked_client.floating_texts();
+        let chat_log = locked_client.chat_log();
+        let (ping_ms, packet_loss) = (locked_client.ping_ms(), locked_client.packet_loss());
+        drop(locked_client);
+
+        if lost.is_none()
+            && let Some(reason) = connection.loading_failure()
+        {
+            connection.send(ClientMessage::Quit);
+            lost = Some(reason);
+        }
+        let joined = game_state.is_some();
+
+        match (&map, game_state, player) {
+            (Some(map), Some(game_state), Some(player)) => {
+                render::draw_game(&mut screen, map, &game_state, &player);
+                render::draw_floating_texts(&mut screen, &floating_texts, &player);
+                render::draw_kill_feed(&mut screen, &kill_feed, &player.name);
+                render::draw_chat(&mut screen, &chat_log, input.chat());
+                render::draw_net_status(&mut screen, ping_ms, packet_loss);
+                if input.debug() {
+                    let viewport = connection.relevance_viewport();
+                    render::draw_debug(&mut screen, &connection.state(), &player, viewport, fps);
+                }
+                if input.scoreboard() {
+                    let locked_client = connection.state();
+                    let entries = locked_client.scoreboard();
+                    drop(locked_client);
+                    render::draw_scoreboard(&mut screen, &entries, Some(player.id));
+                }
+            }
+            _ => {
+                if last_update.elapsed() > Duration::from_millis(300) {
+                    loading_frame += 1;
+                    last_update = Instant::now();
+                }
+                render::draw_loading(&mut screen, loading_frame, connection.map_progress());
+            }
+        }
+
+        if joined && connection.is_interrupted() {
+            let silence = connection.state().silence();
+            render::draw_interrupted(&mut screen, silence, reconnector.attempts());
+        }
+
+        screen.flush(&mut stdout)?;
+        input.read(config::TUI_FRAME_INTERVAL.saturating_sub(frame_start.elapsed()))?;
+    }
+}
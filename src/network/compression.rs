@@ -0,0 +1,33 @@
+use lz4_flex::block;
+
+// Upper bound for a decompressed payload, so a forged size prefix cannot make us allocate gigabytes.
+const MAX_DECOMPRESSED_SIZE: usize = 1024 * 1024;
+
+/// Compresses `src` into `dst` as LZ4 with a little-endian u32 size prefix.
+/// Returns false when the result would not be smaller than the input.
+pub fn compress_into(src: &[u8], dst: &mut Vec<u8>) -> bool {
+    dst.clear();
+    dst.extend_from_slice(&(src.len() as u32).to_le_bytes());
+    dst.resize(4 + block::get_maximum_output_size(src.len()), 0);
+
+    match block::compress_into(src, &mut dst[4..]) {
+        Ok(size) => {
+            dst.truncate(4 + size);
+            dst.len() < src.len()
+        }
+        Err(_) => false,
+    }
+}
+
+pub fn decompress_into(src: &[u8], dst: &mut Vec<u8>) -> bool {
+    let Ok((size, data)) = block::uncompressed_size(src) else {
+        return false;
+    };
+    if size > MAX_DECOMPRESSED_SIZE {
+        return false;
+    }
+
+    dst.clear();
+    dst.resize(size, 0);
+    matches!(block::decompress_into(data, dst), Ok(written) if written == size)
+}
@@ -0,0 +1,124 @@
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+use super::send_message;
+
+const DISCOVERY_MAGIC: u32 = 0x7465_726d;
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum DiscoveryMessage {
+    Probe {
+        magic: u32,
+    },
+    Announce {
+        magic: u32,
+        info: ServerAnnouncement,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ServerAnnouncement {
+    pub instance_id: u64,
+    pub name: String,
+    pub map_width: usize,
+    pub map_height: usize,
+    pub players: u32,
+    pub port: u16,
+    pub tcp_port: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiscoveredServer {
+    pub addr: SocketAddr,
+    pub info: ServerAnnouncement,
+}
+
+impl DiscoveryMessage {
+    pub fn probe() -> Self {
+        DiscoveryMessage::Probe {
+            magic: DISCOVERY_MAGIC,
+        }
+    }
+
+    pub fn announce(info: ServerAnnouncement) -> Self {
+        DiscoveryMessage::Announce {
+            magic: DISCOVERY_MAGIC,
+            info,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            DiscoveryMessage::Probe { magic } | DiscoveryMessage::Announce { magic, .. } => {
+                *magic == DISCOVERY_MAGIC
+            }
+        }
+    }
+}
+
+pub fn discover_servers(timeout: Duration) -> Vec<DiscoveredServer> {
+    let mut servers: Vec<DiscoveredServer> = Vec::new();
+
+    let socket = match UdpSocket::bind("0.0.0.0:0") {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("Failed to bind discovery socket: {:?}", e);
+            return servers;
+        }
+    };
+    if let Err(e) = socket.set_broadcast(true) {
+        eprintln!("Failed to enable broadcast: {:?}", e);
+        return servers;
+    }
+
+    let port = config::DISCOVERY_PORT as u16;
+    send_message(
+        &socket,
+        &DiscoveryMessage::probe(),
+        SocketAddr::from((Ipv4Addr::BROADCAST, port)),
+    );
+    send_message(
+        &socket,
+        &DiscoveryMessage::probe(),
+        SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+    );
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 1024];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining)).ok();
+
+        let Ok((amt, src)) = socket.recv_from(&mut buf) else {
+            break;
+        };
+        let Ok(DiscoveryMessage::Announce { magic, info }) =
+            bincode::deserialize::<DiscoveryMessage>(&buf[..amt])
+        else {
+            continue;
+        };
+        if magic != DISCOVERY_MAGIC {
+            continue;
+        }
+
+        if servers
+            .iter()
+            .any(|s| s.info.instance_id == info.instance_id)
+        {
+            continue;
+        }
+        let addr = SocketAddr::new(src.ip(), info.port);
+        servers.push(DiscoveredServer { addr, info });
+    }
+
+    servers
+}
@@ -1,11 +1,28 @@
+pub mod compression;
+pub mod discovery;
+pub mod packet;
+pub mod registry;
+pub mod reliable;
 pub mod state;
+use crate::config;
 use serde::{Serialize, de::DeserializeOwned};
+use std::cell::RefCell;
+use std::io::{ErrorKind, Read, Write};
 use std::net::SocketAddr;
+use std::net::TcpStream;
 use std::net::UdpSocket;
 
+/// Twice the serialized map (4 bytes a tile); no real frame comes close.
+const MAX_TCP_FRAME_SIZE: usize = config::MAP_WIDTH * config::MAP_HEIGHT * 8;
+const MAX_DATAGRAM_SIZE: usize = 65536;
+
+thread_local! {
+    static RECV_BUF: RefCell<Vec<u8>> = RefCell::new(vec![0u8; MAX_DATAGRAM_SIZE]);
+    static SEND_BUF: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
 pub fn recv_message<T: DeserializeOwned>(socket: &UdpSocket) -> Option<(T, SocketAddr)> {
-    let mut buf = [0u8; 65536];
-    match socket.recv_from(&mut buf) {
+    RECV_BUF.with_borrow_mut(|buf| match socket.recv_from(buf) {
         Ok((amt, src)) => match bincode::deserialize::<T>(&buf[..amt]) {
             Ok(msg) => Some((msg, src)),
             Err(e) => {
@@ -13,22 +30,73 @@ pub fn recv_message<T: DeserializeOwned>(socket: &UdpSocket) -> Option<(T, Socke
                 None
             }
         },
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => None,
         Err(e) => {
             eprintln!("Failed to receive from socket: {:?}", e);
             None
         }
-    }
+    })
 }
 
 pub fn send_message<T: Serialize>(socket: &UdpSocket, msg: &T, target: SocketAddr) -> bool {
-    match bincode::serialize(msg) {
-        Ok(data) => match socket.send_to(&data, target) {
+    SEND_BUF.with_borrow_mut(|data| {
+        data.clear();
+        if let Err(e) = bincode::serialize_into(&mut *data, msg) {
+            eprintln!("Failed to serialize message: {:?}", e);
+            return false;
+        }
+        match socket.send_to(data, target) {
             Ok(_) => true,
             Err(e) => {
                 eprintln!("Failed to send message: {:?}", e);
                 false
             }
-        },
+        }
+    })
+}
+
+pub fn recv_tcp_message<T: DeserializeOwned>(stream: &mut TcpStream) -> Option<T> {
+    let mut len_buf = [0u8; 4];
+    if let Err(e) = stream.read_exact(&mut len_buf) {
+        if e.kind() != ErrorKind::UnexpectedEof {
+            eprintln!("Failed to receive from stream: {:?}", e);
+        }
+        return None;
+    }
+
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > MAX_TCP_FRAME_SIZE {
+        eprintln!("TCP frame too large: {} bytes", len);
+        return None;
+    }
+
+    let mut buf = vec![0u8; len];
+    if let Err(e) = stream.read_exact(&mut buf) {
+        eprintln!("Failed to receive from stream: {:?}", e);
+        return None;
+    }
+
+    match bincode::deserialize::<T>(&buf) {
+        Ok(msg) => Some(msg),
+        Err(e) => {
+            eprintln!("Failed to deserialize message: {:?}", e);
+            None
+        }
+    }
+}
+
+pub fn send_tcp_message<T: Serialize>(stream: &mut TcpStream, msg: &T) -> bool {
+    match bincode::serialize(msg) {
+        Ok(data) => {
+            let len = (data.len() as u32).to_be_bytes();
+            match stream.write_all(&len).and_then(|_| stream.write_all(&data)) {
+                Ok(_) => true,
+                Err(e) => {
+                    eprintln!("Failed to send message: {:?}", e);
+                    false
+                }
+            }
+        }
         Err(e) => {
             eprintln!("Failed to serialize message: {:?}", e);
             false
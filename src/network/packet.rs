@@ -0,0 +1,271 @@
+use serde::{Serialize, de::DeserializeOwned};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+use crate::config;
+
+use super::{MAX_DATAGRAM_SIZE, compression};
+
+// Every frame in a datagram starts with its payload length (u16, big endian) and a flags byte.
+const FRAME_HEADER_SIZE: usize = 3;
+const FLAG_COMPRESSED: u8 = 0x01;
+/// The largest UDP payload over IPv4; `send_to` fails with anything bigger.
+const MAX_UDP_PAYLOAD: usize = 65_507;
+const MAX_FRAME_PAYLOAD: usize = MAX_UDP_PAYLOAD - FRAME_HEADER_SIZE;
+
+/// Packs messages for one peer into datagrams of up to `PACKET_MTU` bytes.
+/// A message larger than that still goes out, alone in its own datagram, as
+/// long as it fits in one UDP datagram; bigger ones are dropped and logged.
+pub struct PacketWriter {
+    target: SocketAddr,
+    compress: bool,
+    buf: Vec<u8>,
+    scratch: Vec<u8>,
+    compressed: Vec<u8>,
+}
+
+impl PacketWriter {
+    pub fn new(target: SocketAddr) -> Self {
+        Self {
+            target,
+            compress: false,
+            buf: Vec::with_capacity(config::PACKET_MTU),
+            scratch: Vec::new(),
+            compressed: Vec::new(),
+        }
+    }
+
+    /// Only enable this once the peer has said it can decompress.
+    pub fn set_compression(&mut self, compress: bool) {
+        self.compress = compress;
+    }
+
+    pub fn push<T: Serialize>(&mut self, socket: &UdpSocket, msg: &T) -> bool {
+        self.scratch.clear();
+        if let Err(e) = bincode::serialize_into(&mut self.scratch, msg) {
+            eprintln!("Failed to serialize message: {:?}", e);
+            return false;
+        }
+
+        let compressed = self.compress
+            && self.scratch.len() >= config::COMPRESSION_THRESHOLD
+            && compression::compress_into(&self.scratch, &mut self.compressed);
+        let payload_len = if compressed {
+            self.compressed.len()
+        } else {
+            self.scratch.len()
+        };
+        if payload_len > MAX_FRAME_PAYLOAD {
+            eprintln!("Message too large for a datagram: {} bytes", payload_len);
+            return false;
+        }
+        let len = payload_len as u16;
+
+        if !self.buf.is_empty()
+            && self.buf.len() + FRAME_HEADER_SIZE + payload_len > config::PACKET_MTU
+        {
+            self.flush(socket);
+        }
+        self.buf.extend_from_slice(&len.to_be_bytes());
+        if compressed {
+            self.buf.push(FLAG_COMPRESSED);
+            self.buf.extend_from_slice(&self.compressed);
+        } else {
+            self.buf.push(0);
+            self.buf.extend_from_slice(&self.scratch);
+        }
+        true
+    }
+
+    pub fn flush(&mut self, socket: &UdpSocket) -> bool {
+        if self.buf.is_empty() {
+            return true;
+        }
+
+        let sent = match socket.send_to(&self.buf, self.target) {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("Failed to send message: {:?}", e);
+                false
+            }
+        };
+        self.buf.clear();
+        sent
+    }
+}
+
+/// Receives datagrams written by `PacketWriter` and hands out their messages one by one.
+pub struct PacketReader<T> {
+    buf: Vec<u8>,
+    decompressed: Vec<u8>,
+    pending: VecDeque<(T, usize)>,
+    src: Option<SocketAddr>,
+}
+
+impl<T: DeserializeOwned> Default for PacketReader<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: DeserializeOwned> PacketReader<T> {
+    pub fn new() -> Self {
+        Self {
+            buf: vec![0u8; MAX_DATAGRAM_SIZE],
+            decompressed: Vec::new(),
+            pending: VecDeque::new(),
+            src: None,
+        }
+    }
+
+    pub fn recv(&mut self, socket: &UdpSocket) -> Option<(T, SocketAddr)> {
+        self.recv_sized(socket).map(|(msg, src, _)| (msg, src))
+    }
+
+    /// Like `recv`, also returning the size of the message's frame on the
+    /// wire, header included.
+    pub fn recv_sized(&mut self, socket: &UdpSocket) -> Option<(T, SocketAddr, usize)> {
+        if let Some((msg, size)) = self.pending.pop_front() {
+            return self.src.map(|src| (msg, src, size));
+        }
+
+        let (amt, src) = match socket.recv_from(&mut self.buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return None;
+            }
+            Err(e) => {
+                eprintln!("Failed to receive from socket: {:?}", e);
+                return None;
+            }
+        };
+        self.src = Some(src);
+
+        let mut data = &self.buf[..amt];
+        while data.len() >= FRAME_HEADER_SIZE {
+            let len = u16::from_be_bytes([data[0], data[1]]) as usize;
+            let flags = data[2];
+            let Some(frame) = data.get(FRAME_HEADER_SIZE..FRAME_HEADER_SIZE + len) else {
+                eprintln!("Truncated packet from {}", src);
+                break;
+            };
+            data = &data[FRAME_HEADER_SIZE + len..];
+
+            let payload = if flags & FLAG_COMPRESSED != 0 {
+                if !compression::decompress_into(frame, &mut self.decompressed) {
+                    eprintln!("Failed to decompress message from {}", src);
+                    continue;
+                }
+                &self.decompressed[..]
+            } else {
+                frame
+            };
+            match bincode::deserialize::<T>(payload) {
+                Ok(msg) => self.pending.push_back((msg, FRAME_HEADER_SIZE + len)),
+                Err(e) => eprintln!("Failed to deserialize message: {:?}", e),
+            }
+        }
+
+        self.pending.pop_front().map(|(msg, size)| (msg, src, size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn pair() -> (UdpSocket, UdpSocket) {
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .unwrap();
+        (sender, receiver)
+    }
+
+    fn datagram_sizes(receiver: &UdpSocket) -> Vec<usize> {
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        let mut sizes = Vec::new();
+        while let Ok((amt, _)) = receiver.recv_from(&mut buf) {
+            sizes.push(amt);
+        }
+        sizes
+    }
+
+    #[test]
+    fn batches_messages_up_to_the_mtu() {
+        let (sender, receiver) = pair();
+        let mut writer = PacketWriter::new(receiver.local_addr().unwrap());
+
+        // 8 bytes of length prefix + 500 bytes, plus the frame header.
+        let frame = FRAME_HEADER_SIZE + 508;
+        for i in 0..5u8 {
+            assert!(writer.push(&sender, &vec![i; 500]));
+        }
+        assert!(writer.flush(&sender));
+
+        // Two frames fit in `PACKET_MTU`, a third would not.
+        assert!(2 * frame <= config::PACKET_MTU && 3 * frame > config::PACKET_MTU);
+        assert_eq!(datagram_sizes(&receiver), vec![2 * frame, 2 * frame, frame]);
+    }
+
+    #[test]
+    fn reader_splits_batched_frames() {
+        let (sender, receiver) = pair();
+        let mut writer = PacketWriter::new(receiver.local_addr().unwrap());
+        for i in 0..3u32 {
+            writer.push(&sender, &i);
+        }
+        writer.flush(&sender);
+
+        let mut reader = PacketReader::<u32>::new();
+        for i in 0..3u32 {
+            let (msg, src, size) = reader.recv_sized(&receiver).unwrap();
+            assert_eq!((msg, src), (i, sender.local_addr().unwrap()));
+            assert_eq!(size, FRAME_HEADER_SIZE + 4);
+        }
+        assert!(reader.recv(&receiver).is_none());
+    }
+
+    #[test]
+    fn compressed_frames_round_trip() {
+        let (sender, receiver) = pair();
+        let mut writer = PacketWriter::new(receiver.local_addr().unwrap());
+        writer.set_compression(true);
+        let msg = vec![7u8; config::COMPRESSION_THRESHOLD * 4];
+        assert!(writer.push(&sender, &msg));
+        // Too small to be worth compressing.
+        assert!(writer.push(&sender, &vec![1u8]));
+        writer.flush(&sender);
+
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        let (amt, _) = receiver.recv_from(&mut buf).unwrap();
+        assert_eq!(buf[2] & FLAG_COMPRESSED, FLAG_COMPRESSED);
+        assert!(amt < msg.len());
+
+        sender
+            .send_to(&buf[..amt], receiver.local_addr().unwrap())
+            .unwrap();
+        let mut reader = PacketReader::<Vec<u8>>::new();
+        assert_eq!(reader.recv(&receiver).unwrap().0, msg);
+        assert_eq!(reader.recv(&receiver).unwrap().0, vec![1u8]);
+    }
+
+    #[test]
+    fn oversized_payload_is_rejected() {
+        let (sender, receiver) = pair();
+        let mut writer = PacketWriter::new(receiver.local_addr().unwrap());
+
+        // Fits in the u16 length field but not in a UDP datagram.
+        assert!(!writer.push(&sender, &vec![0u8; MAX_FRAME_PAYLOAD + 1 - 8]));
+        assert!(!writer.push(&sender, &vec![0u8; u16::MAX as usize]));
+        assert!(writer.flush(&sender));
+        assert!(datagram_sizes(&receiver).is_empty());
+
+        assert!(writer.push(&sender, &vec![0u8; MAX_FRAME_PAYLOAD - 8]));
+        assert!(writer.flush(&sender));
+        assert_eq!(datagram_sizes(&receiver), vec![MAX_UDP_PAYLOAD]);
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+use super::{recv_message, send_message};
+
+const LIST_ATTEMPTS: u32 = 3;
+const LIST_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum RegistryMessage {
+    Heartbeat(ServerHeartbeat),
+    List,
+    Servers(Vec<RegisteredServer>),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ServerHeartbeat {
+    pub name: String,
+    pub mode: String,
+    pub players: u32,
+    pub port: u16,
+    pub tcp_port: u16,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct RegisteredServer {
+    pub addr: SocketAddr,
+    pub name: String,
+    pub mode: String,
+    pub players: u32,
+    pub tcp_port: u16,
+}
+
+pub fn send_heartbeat(
+    socket: &UdpSocket,
+    registry: SocketAddr,
+    heartbeat: ServerHeartbeat,
+) -> bool {
+    send_message(socket, &RegistryMessage::Heartbeat(heartbeat), registry)
+}
+
+pub fn list_servers(registry: SocketAddr) -> Option<Vec<RegisteredServer>> {
+    let socket = utils::bind_udp_for(registry).ok()?;
+    socket.set_read_timeout(Some(LIST_TIMEOUT)).ok()?;
+
+    for _ in 0..LIST_ATTEMPTS {
+        send_message(&socket, &RegistryMessage::List, registry);
+
+        while let Some((msg, src)) = recv_message::<RegistryMessage>(&socket) {
+            if src != registry {
+                continue;
+            }
+            if let RegistryMessage::Servers(servers) = msg {
+                return Some(servers);
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Sender half of a reliable ordered stream over datagrams. Items are
+/// numbered and kept until acknowledged; `due` hands out the unacknowledged
+/// ones when new items are waiting or the resend interval has passed.
+pub struct ReliableSender<T> {
+    next_seq: u32,
+    sent_upto: u32,
+    unacked: VecDeque<(u32, T)>,
+    last_sent: Option<Instant>,
+    capacity: usize,
+    resend_interval: Duration,
+}
+
+impl<T: Clone> ReliableSender<T> {
+    pub fn new(capacity: usize, resend_interval: Duration) -> Self {
+        Self {
+            next_seq: 0,
+            sent_upto: 0,
+            unacked: VecDeque::new(),
+            last_sent: None,
+            capacity,
+            resend_interval,
+        }
+    }
+
+    /// Queues `item`. When the peer stops acknowledging, the oldest items
+    /// are dropped once `capacity` is reached.
+    pub fn push(&mut self, item: T) {
+        if self.unacked.len() >= self.capacity {
+            self.unacked.pop_front();
+        }
+        self.unacked.push_back((self.next_seq, item));
+        self.next_seq = self.next_seq.wrapping_add(1);
+    }
+
+    /// Everything before `next_expected` has arrived.
+    pub fn ack(&mut self, next_expected: u32) {
+        while let Some((seq, _)) = self.unacked.front() {
+            if (next_expected.wrapping_sub(*seq) as i32) > 0 {
+                self.unacked.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The first sequence number and up to `max_items` items to send now.
+    /// Pages always start at the oldest unacknowledged item, so items past
+    /// the first page wait until it is acknowledged.
+    pub fn due(&mut self, now: Instant, max_items: usize) -> Option<(u32, Vec<T>)> {
+        let (first_seq, _) = self.unacked.front()?;
+        let first_seq = *first_seq;
+
+        let page_len = self.unacked.len().min(max_items);
+        let page_end = first_seq.wrapping_add(page_len as u32);
+        let has_new = (page_end.wrapping_sub(self.sent_upto) as i32) > 0;
+        let resend = self
+            .last_sent
+            .is_none_or(|at| now.duration_since(at) >= self.resend_interval);
+        if !has_new && !resend {
+            return None;
+        }
+
+        let items: Vec<T> = self
+            .unacked
+            .iter()
+            .take(page_len)
+            .map(|(_, item)| item.clone())
+            .collect();
+        self.sent_upto = page_end;
+        self.last_sent = Some(now);
+        Some((first_seq, items))
+    }
+}
+
+/// Receiver half: drops duplicates so items are seen once and in sequence.
+#[derive(Debug, Default)]
+pub struct ReliableReceiver {
+    next_expected: u32,
+}
+
+impl ReliableReceiver {
+    /// Returns the items of a batch starting at `first_seq` not seen before.
+    pub fn accept<T>(&mut self, first_seq: u32, items: Vec<T>) -> Vec<T> {
+        // Batches always start at the oldest unacknowledged item, so a gap
+        // means the sender dropped items past its capacity: skip over them.
+        let skip = (self.next_expected.wrapping_sub(first_seq) as i32).max(0) as usize;
+        let end = first_seq.wrapping_add(items.len() as u32);
+        if skip >= items.len() {
+            return Vec::new();
+        }
+        self.next_expected = end;
+        items.into_iter().skip(skip).collect()
+    }
+
+    /// Value to acknowledge with.
+    pub fn next_expected(&self) -> u32 {
+        self.next_expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESEND: Duration = Duration::from_millis(100);
+
+    fn sender_with(items: &[u32]) -> ReliableSender<u32> {
+        let mut sender = ReliableSender::new(16, RESEND);
+        for &item in items {
+            sender.push(item);
+        }
+        sender
+    }
+
+    #[test]
+    fn new_items_go_out_at_once_and_then_wait_for_the_resend_interval() {
+        let now = Instant::now();
+        let mut sender = sender_with(&[10, 11]);
+
+        assert_eq!(sender.due(now, 8), Some((0, vec![10, 11])));
+        assert_eq!(sender.due(now, 8), None);
+
+        sender.push(12);
+        assert_eq!(sender.due(now, 8), Some((0, vec![10, 11, 12])));
+        assert_eq!(sender.due(now + RESEND / 2, 8), None);
+        assert_eq!(sender.due(now + RESEND, 8), Some((0, vec![10, 11, 12])));
+    }
+
+    #[test]
+    fn backlog_larger_than_a_page_is_sent_page_by_page() {
+        let now = Instant::now();
+        let mut sender = sender_with(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(sender.due(now, 4), Some((0, vec![0, 1, 2, 3])));
+        // The rest waits for an ack instead of resending the first page.
+        assert_eq!(sender.due(now, 4), None);
+
+        sender.ack(4);
+        assert_eq!(sender.due(now, 4), Some((4, vec![4, 5, 6, 7])));
+        sender.ack(8);
+        assert_eq!(sender.due(now, 4), Some((8, vec![8, 9])));
+        sender.ack(10);
+        assert_eq!(sender.due(now + RESEND, 4), None);
+    }
+
+    #[test]
+    fn partial_ack_resends_from_the_oldest_unacknowledged_item() {
+        let now = Instant::now();
+        let mut sender = sender_with(&[0, 1, 2]);
+        sender.due(now, 8);
+
+        sender.ack(2);
+        assert_eq!(sender.due(now, 8), None);
+        assert_eq!(sender.due(now + RESEND, 8), Some((2, vec![2])));
+
+        // Stale acks are ignored.
+        sender.ack(1);
+        assert_eq!(sender.due(now + RESEND * 2, 8), Some((2, vec![2])));
+    }
+
+    #[test]
+    fn receiver_drops_duplicates() {
+        let mut receiver = ReliableReceiver::default();
+
+        assert_eq!(receiver.accept(0, vec!['a', 'b']), vec!['a', 'b']);
+        assert_eq!(receiver.accept(0, vec!['a', 'b']), Vec::<char>::new());
+        assert_eq!(receiver.accept(0, vec!['a', 'b', 'c']), vec!['c']);
+        assert_eq!(receiver.accept(1, vec!['b']), Vec::<char>::new());
+        assert_eq!(receiver.next_expected(), 3);
+    }
+
+    #[test]
+    fn receiver_skips_items_the_sender_dropped() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(3, RESEND);
+        for item in 0..5 {
+            sender.push(item);
+        }
+        let mut receiver = ReliableReceiver::default();
+
+        let (first_seq, items) = sender.due(now, 8).unwrap();
+        assert_eq!((first_seq, items.clone()), (2, vec![2, 3, 4]));
+        assert_eq!(receiver.accept(first_seq, items), vec![2, 3, 4]);
+        sender.ack(receiver.next_expected());
+        assert_eq!(sender.due(now + RESEND, 8), None);
+    }
+
+    #[test]
+    fn sequence_numbers_wrap_around() {
+        let now = Instant::now();
+        let mut sender = ReliableSender::new(16, RESEND);
+        sender.next_seq = u32::MAX - 1;
+        sender.sent_upto = u32::MAX - 1;
+        let mut receiver = ReliableReceiver {
+            next_expected: u32::MAX - 1,
+        };
+        for item in 0..4 {
+            sender.push(item);
+        }
+
+        let (first_seq, items) = sender.due(now, 3).unwrap();
+        assert_eq!(first_seq, u32::MAX - 1);
+        assert_eq!(receiver.accept(first_seq, items), vec![0, 1, 2]);
+        assert_eq!(receiver.next_expected(), 1);
+        assert_eq!(sender.due(now, 3), None);
+
+        sender.ack(receiver.next_expected());
+        let (first_seq, items) = sender.due(now, 3).unwrap();
+        assert_eq!((first_seq, items.clone()), (1, vec![3]));
+        assert_eq!(receiver.accept(first_seq, items), vec![3]);
+
+        // A late duplicate from before the wrap is still recognised.
+        assert_eq!(
+            receiver.accept(u32::MAX - 1, vec![0, 1, 2, 3]),
+            Vec::<i32>::new()
+        );
+        sender.ack(receiver.next_expected());
+        assert_eq!(sender.due(now + RESEND, 3), None);
+    }
+}
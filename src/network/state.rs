@@ -6,7 +6,9 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    config,
     game::{
+        event::GameEvent,
         player::Player,
         state::{Direction, GameState, GameStateDiff},
     },
@@ -21,19 +23,171 @@ pub struct ServerMessage {
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum ServerMessageType {
-    InitPlayer(Player),
+    /// Reply to a UDP `Init`; `token` rebinds to the player like `Welcome`'s.
+    InitPlayer {
+        player: Box<Player>,
+        token: u64,
+    },
     Map(MapChunk),
-    GameState(GameState),
+    GameState(Box<GameState>),
     GameStateDiff(GameStateDiff),
+    Welcome {
+        player: Box<Player>,
+        token: u64,
+    },
+    Admin(String),
+    Info(ServerInfo),
+    Hello(Capabilities),
+    Shutdown(String),
+    /// One page of the scoreboard; pages arrive every `SCOREBOARD_INTERVAL`.
+    /// `total` is the number of players on the server.
+    Scoreboard {
+        total: u32,
+        entries: Vec<ScoreboardEntry>,
+    },
+    /// Answered with `ClientMessage::Pong` so the server can measure ping.
+    Ping(u32),
+    /// Game events numbered from `first_seq`, resent until acknowledged with
+    /// `ClientMessage::EventAck`.
+    Events {
+        first_seq: u32,
+        events: Vec<GameEvent>,
+    },
+    /// Chat messages numbered from `first_seq`, resent until acknowledged
+    /// with `ClientMessage::ChatAck`.
+    Chat {
+        first_seq: u32,
+        messages: Vec<ChatMessage>,
+    },
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub enum ClientMessage {
-    Init,
+    /// Join with the requested nickname; the server may change it.
+    Init(String),
     Map(HashSet<u32>),
     Quit,
     Move(Direction),
     Shoot,
+    Bind(u64),
+    Admin(AdminCommand),
+    Query,
+    NetStats {
+        last_seq: u32,
+        received: u32,
+    },
+    Viewport {
+        half_w: f32,
+        half_h: f32,
+    },
+    Hello(Capabilities),
+    Resync,
+    Pong(u32),
+    /// Sequence number of the next game event the client expects.
+    EventAck(u32),
+    /// Text to broadcast; checked with `sanitize_chat`.
+    Chat(String),
+    /// Sequence number of the next chat message the client expects.
+    ChatAck(u32),
+}
+
+/// Optional protocol features. The client sends what it supports in `Hello`
+/// before `Init`/`Bind`; the server answers with what both sides will use.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Capabilities {
+    pub compression: bool,
+}
+
+impl Capabilities {
+    pub fn supported() -> Self {
+        Self { compression: true }
+    }
+
+    pub fn negotiate(&self, other: &Capabilities) -> Self {
+        Self {
+            compression: self.compression && other.compression,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub enum AdminCommand {
+    Players,
+    Kick(u32),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ServerInfo {
+    pub name: String,
+    pub mode: String,
+    pub map_width: usize,
+    pub map_height: usize,
+    pub players: Vec<PlayerScore>,
+    pub uptime_secs: u64,
+    pub desyncs: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PlayerScore {
+    pub id: u32,
+    pub name: String,
+    pub kills: u32,
+    pub deths: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScoreboardEntry {
+    pub id: u32,
+    pub name: String,
+    pub kills: u32,
+    pub deths: u32,
+    pub ping_ms: Option<u32>,
+    pub bullet_damage: u32,
+    pub fire_rate: f32,
+    pub walk_speed: f32,
+}
+
+/// A chat line as broadcast by the server. `sender` is `None` for notices
+/// from the server itself.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub sender: Option<u32>,
+    pub name: String,
+    pub text: String,
+    /// Seconds since the Unix epoch, by the server clock.
+    pub timestamp: u64,
+}
+
+impl ChatMessage {
+    /// `HH:MM` of the timestamp in UTC.
+    pub fn time_of_day(&self) -> String {
+        let minutes = self.timestamp / 60;
+        format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+    }
+}
+
+/// Drops control characters, trims and cuts the text to `MAX_CHAT_LEN`;
+/// `None` when nothing is left.
+pub fn sanitize_chat(text: &str) -> Option<String> {
+    let text: String = text
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .chars()
+        .take(config::MAX_CHAT_LEN)
+        .collect();
+    (!text.is_empty()).then_some(text)
+}
+
+impl ScoreboardEntry {
+    pub fn score(&self) -> u32 {
+        self.kills
+    }
+
+    pub fn kill_death_ratio(&self) -> f32 {
+        self.kills as f32 / self.deths.max(1) as f32
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -93,3 +247,24 @@ impl MapDownloader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_chat_strips_control_characters() {
+        assert_eq!(
+            sanitize_chat("  hi\u{1b}[2J there\r\n"),
+            Some("hi[2J there".to_string())
+        );
+        assert_eq!(sanitize_chat("\u{7}\n\t "), None);
+    }
+
+    #[test]
+    fn sanitize_chat_truncates_at_the_limit() {
+        let long = "ж".repeat(config::MAX_CHAT_LEN + 10);
+        let text = sanitize_chat(&long).unwrap();
+        assert_eq!(text.chars().count(), config::MAX_CHAT_LEN);
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config,
+    network::{
+        recv_message,
+        registry::{RegisteredServer, RegistryMessage, ServerHeartbeat},
+        send_message,
+    },
+    utils,
+};
+
+#[derive(Debug)]
+pub struct Registry {
+    pub ttl: Duration,
+    pub servers: HashMap<SocketAddr, (RegisteredServer, Instant)>,
+}
+
+impl Registry {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            servers: HashMap::new(),
+        }
+    }
+
+    pub fn heartbeat(&mut self, src: SocketAddr, heartbeat: ServerHeartbeat) {
+        let addr = SocketAddr::new(src.ip().to_canonical(), heartbeat.port);
+        let server = RegisteredServer {
+            addr,
+            name: heartbeat.name,
+            mode: heartbeat.mode,
+            players: heartbeat.players,
+            tcp_port: heartbeat.tcp_port,
+        };
+
+        if self
+            .servers
+            .insert(addr, (server, Instant::now()))
+            .is_none()
+        {
+            println!("Server registered: {}", addr);
+        }
+    }
+
+    pub fn expire(&mut self) {
+        let ttl = self.ttl;
+        self.servers.retain(|addr, (_, last_seen)| {
+            let alive = last_seen.elapsed() < ttl;
+            if !alive {
+                println!("Server expired: {}", addr);
+            }
+            alive
+        });
+    }
+
+    pub fn list(&self) -> Vec<RegisteredServer> {
+        let mut servers: Vec<_> = self.servers.values().map(|(s, _)| s.clone()).collect();
+        servers.sort_by_key(|s| s.addr);
+        servers
+    }
+}
+
+pub fn run_registry(port: u16) {
+    let socket = utils::bind_udp(SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port))
+        .or_else(|_| utils::bind_udp(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port)))
+        .expect("Could not bind registry socket");
+    socket
+        .set_read_timeout(Some(config::REGISTRY_HEARTBEAT_INTERVAL))
+        .expect("Failed to set read timeout");
+    println!("Registry running on port {}", port);
+
+    serve(&socket, config::REGISTRY_SERVER_TTL);
+}
+
+/// Answers heartbeats and list requests on `socket` forever.
+pub fn serve(socket: &UdpSocket, ttl: Duration) {
+    let mut registry = Registry::new(ttl);
+    loop {
+        let received = recv_message::<RegistryMessage>(socket);
+        registry.expire();
+
+        let Some((msg, src)) = received else {
+            continue;
+        };
+        match msg {
+            RegistryMessage::Heartbeat(heartbeat) => registry.heartbeat(src, heartbeat),
+            RegistryMessage::List => {
+                send_message(socket, &RegistryMessage::Servers(registry.list()), src);
+            }
+            RegistryMessage::Servers(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Ipv4Addr, thread};
+
+    use crate::network::registry::{list_servers, send_heartbeat};
+
+    use super::*;
+
+    fn heartbeat(name: &str, players: u32, port: u16) -> ServerHeartbeat {
+        ServerHeartbeat {
+            name: name.to_string(),
+            mode: config::GAME_MODE.to_string(),
+            players,
+            port,
+            tcp_port: port - 1,
+        }
+    }
+
+    fn src(addr: &str) -> SocketAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn heartbeat_registers_source_ip_with_announced_port() {
+        let mut registry = Registry::new(config::REGISTRY_SERVER_TTL);
+        registry.heartbeat(src("[::ffff:10.0.0.5]:40000"), heartbeat("a", 2, 8888));
+
+        let servers = registry.list();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].addr, src("10.0.0.5:8888"));
+        assert_eq!(servers[0].name, "a");
+        assert_eq!(servers[0].players, 2);
+        assert_eq!(servers[0].tcp_port, 8887);
+    }
+
+    #[test]
+    fn repeated_heartbeat_updates_the_entry() {
+        let mut registry = Registry::new(config::REGISTRY_SERVER_TTL);
+        registry.heartbeat(src("10.0.0.5:40000"), heartbeat("a", 1, 8888));
+        registry.heartbeat(src("10.0.0.5:40001"), heartbeat("a", 3, 8888));
+
+        let servers = registry.list();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].players, 3);
+    }
+
+    #[test]
+    fn list_is_sorted_by_address() {
+        let mut registry = Registry::new(config::REGISTRY_SERVER_TTL);
+        registry.heartbeat(src("10.0.0.7:1"), heartbeat("c", 0, 9000));
+        registry.heartbeat(src("10.0.0.5:1"), heartbeat("a", 0, 9000));
+        registry.heartbeat(src("10.0.0.5:1"), heartbeat("b", 0, 8000));
+
+        let names: Vec<_> = registry.list().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn expire_drops_servers_silent_for_the_ttl() {
+        let ttl = Duration::from_secs(15);
+        let mut registry = Registry::new(ttl);
+        registry.heartbeat(src("10.0.0.5:1"), heartbeat("old", 0, 8888));
+        registry.heartbeat(src("10.0.0.6:1"), heartbeat("fresh", 0, 8888));
+        let (_, last_seen) = registry.servers.get_mut(&src("10.0.0.5:8888")).unwrap();
+        *last_seen -= ttl;
+
+        registry.expire();
+
+        let names: Vec<_> = registry.list().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["fresh"]);
+    }
+
+    #[test]
+    fn lists_servers_over_localhost() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let registry_addr = socket.local_addr().unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        thread::spawn(move || serve(&socket, config::REGISTRY_SERVER_TTL));
+
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        assert!(send_heartbeat(
+            &server,
+            registry_addr,
+            heartbeat("local", 4, 8888)
+        ));
+
+        // The heartbeat and the list request race; retry until it shows up.
+        let mut servers = Vec::new();
+        for _ in 0..20 {
+            servers = list_servers(registry_addr).unwrap();
+            if !servers.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(servers.len(), 1);
+        assert_eq!(
+            servers[0].addr,
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 8888))
+        );
+        assert_eq!(servers[0].players, 4);
+    }
+}
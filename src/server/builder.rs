@@ -0,0 +1,84 @@
+use std::{
+    io,
+    net::{IpAddr, SocketAddr},
+};
+
+use crate::config;
+
+use super::{ServerConfig, ServerHandle};
+
+/// Configures and starts a server without blocking the caller. Use port 0 to
+/// let the OS pick free ports; `ServerHandle` reports what was bound.
+pub struct ServerBuilder {
+    config: ServerConfig,
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServerBuilder {
+    pub fn new() -> Self {
+        Self::with_config(ServerConfig::new(
+            config::UDP_PORT as u16,
+            config::TCP_PORT as u16,
+        ))
+    }
+
+    pub fn with_config(config: ServerConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.config.port = port;
+        self
+    }
+
+    pub fn tcp_port(mut self, tcp_port: u16) -> Self {
+        self.config.tcp_port = tcp_port;
+        self
+    }
+
+    pub fn bind(mut self, ip: IpAddr) -> Self {
+        self.config.bind.push(ip);
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = name.into();
+        self
+    }
+
+    pub fn registry(mut self, registry: SocketAddr) -> Self {
+        self.config.registry = Some(registry);
+        self
+    }
+
+    pub fn tick_rate(mut self, tick_rate: u32) -> Self {
+        self.config.tick_rate = tick_rate;
+        self
+    }
+
+    pub fn snapshot_rate(mut self, snapshot_rate: u32) -> Self {
+        self.config.snapshot_rate = snapshot_rate;
+        self
+    }
+
+    pub fn compression(mut self, compression: bool) -> Self {
+        self.config.compression = compression;
+        self
+    }
+
+    /// Answer LAN discovery probes.
+    pub fn discovery(mut self, discovery: bool) -> Self {
+        self.config.discovery = discovery;
+        self
+    }
+
+    /// Binds all sockets and starts the server threads.
+    pub fn start(self) -> io::Result<ServerHandle> {
+        super::start(self.config)
+    }
+}
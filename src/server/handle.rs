@@ -0,0 +1,108 @@
+use std::{
+    mem,
+    net::SocketAddr,
+    sync::mpsc::{Receiver, Sender},
+    thread::JoinHandle,
+};
+
+/// Shutdown reason sent to clients when a `ServerHandle` is dropped.
+const DROPPED_REASON: &str = "Server stopped";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerEvent {
+    PlayerJoined { id: u32 },
+    PlayerLeft { id: u32 },
+    PlayerKilled { killer: u32, victim: u32 },
+    ChatMessage { id: u32, text: String },
+}
+
+/// A running server started with `ServerBuilder::start`. Dropping it stops
+/// the server like `stop` and waits for its sockets to close.
+pub struct ServerHandle {
+    udp_addrs: Vec<SocketAddr>,
+    tcp_addrs: Vec<SocketAddr>,
+    events: Receiver<ServerEvent>,
+    shutdown: Sender<String>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+/// Stops a server from another thread, e.g. a signal handler.
+#[derive(Clone)]
+pub struct ServerStopper {
+    shutdown: Sender<String>,
+}
+
+impl ServerHandle {
+    pub(super) fn new(
+        udp_addrs: Vec<SocketAddr>,
+        tcp_addrs: Vec<SocketAddr>,
+        events: Receiver<ServerEvent>,
+        shutdown: Sender<String>,
+        threads: Vec<JoinHandle<()>>,
+    ) -> Self {
+        Self {
+            udp_addrs,
+            tcp_addrs,
+            events,
+            shutdown,
+            threads,
+        }
+    }
+
+    /// The first bound UDP address, with the real port if 0 was requested.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.udp_addrs[0]
+    }
+
+    pub fn local_addrs(&self) -> &[SocketAddr] {
+        &self.udp_addrs
+    }
+
+    pub fn tcp_addr(&self) -> SocketAddr {
+        self.tcp_addrs[0]
+    }
+
+    /// Events are dropped when nobody reads them and the queue is full.
+    pub fn events(&self) -> &Receiver<ServerEvent> {
+        &self.events
+    }
+
+    pub fn stopper(&self) -> ServerStopper {
+        ServerStopper {
+            shutdown: self.shutdown.clone(),
+        }
+    }
+
+    /// Notifies connected clients, stops the server and waits until its sockets are closed.
+    pub fn stop(mut self, reason: &str) {
+        let _ = self.shutdown.send(reason.to_string());
+        self.join();
+    }
+
+    /// Blocks until the server has been stopped.
+    pub fn wait(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        for thread in mem::take(&mut self.threads) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        if self.threads.is_empty() {
+            return;
+        }
+        let _ = self.shutdown.send(DROPPED_REASON.to_string());
+        self.join();
+    }
+}
+
+impl ServerStopper {
+    pub fn stop(&self, reason: &str) {
+        let _ = self.shutdown.send(reason.to_string());
+    }
+}
@@ -1,193 +1,380 @@
+mod builder;
+mod handle;
+mod outbound;
+mod send_rate;
+mod simulation;
+mod tcp;
+
+use ::rand::{Rng, thread_rng};
 use std::{
-    collections::HashMap,
-    net::{SocketAddr, UdpSocket},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, UdpSocket},
     sync::{
-        Arc, Mutex,
-        mpsc::{self},
+        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
     },
     thread,
-    time::{Duration, Instant},
 };
 
-use crate::{config, network::state::ServerMessageType};
+use crate::{config, network::state::ServerMessageType, utils};
 use crate::{
-    game::state::GameState,
     map::Map,
     network::{
-        recv_message, send_message,
-        state::{ClientMessage, ServerMessage},
+        discovery::{DiscoveryMessage, ServerAnnouncement},
+        packet::PacketReader,
+        recv_message,
+        registry::{ServerHeartbeat, send_heartbeat},
+        send_message,
+        state::{ClientMessage, MapChunk},
     },
 };
 
-type SharedGameState = Arc<Mutex<GameState>>;
-type SharedClients = Arc<Mutex<HashMap<SocketAddr, u32>>>;
+pub use builder::ServerBuilder;
+pub use handle::{ServerEvent, ServerHandle, ServerStopper};
+use outbound::Outbound;
+use simulation::{ServerInput, Simulation};
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    pub bind: Vec<IpAddr>,
+    pub port: u16,
+    pub tcp_port: u16,
+    pub name: String,
+    pub registry: Option<SocketAddr>,
+    pub tick_rate: u32,
+    pub snapshot_rate: u32,
+    pub compression: bool,
+    pub discovery: bool,
+}
+
+impl ServerConfig {
+    pub fn new(port: u16, tcp_port: u16) -> Self {
+        Self {
+            bind: Vec::new(),
+            port,
+            tcp_port,
+            name: config::SERVER_NAME.to_string(),
+            registry: None,
+            tick_rate: config::TICK_RATE,
+            snapshot_rate: config::SNAPSHOT_RATE,
+            compression: true,
+            discovery: true,
+        }
+    }
+
+    pub fn bind_addresses(&self) -> Vec<IpAddr> {
+        if self.bind.is_empty() {
+            vec![IpAddr::V6(Ipv6Addr::UNSPECIFIED)]
+        } else {
+            self.bind.clone()
+        }
+    }
+}
+
+pub(crate) fn start(server_config: ServerConfig) -> io::Result<ServerHandle> {
+    let ServerConfig {
+        mut port,
+        mut tcp_port,
+        name,
+        registry,
+        ..
+    } = server_config.clone();
+
+    let mut sockets = Vec::new();
+    let mut listeners = Vec::new();
+    for ip in server_config.bind_addresses() {
+        let (socket, listener) = match bind_server_sockets(ip, port, tcp_port) {
+            Ok(bound) => bound,
+            Err(e) if ip == IpAddr::V6(Ipv6Addr::UNSPECIFIED) && server_config.bind.is_empty() => {
+                eprintln!("Dual-stack bind failed ({:?}), falling back to IPv4", e);
+                bind_server_sockets(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port, tcp_port)?
+            }
+            Err(e) => return Err(e),
+        };
+        // With port 0 the OS picks one; reuse it for the remaining addresses.
+        port = socket.local_addr()?.port();
+        tcp_port = listener.local_addr()?.port();
+        println!("Listening on {} (tcp {})", socket.local_addr()?, tcp_port);
+        sockets.push(socket);
+        listeners.push(listener);
+    }
 
-pub fn run_server(port: String) {
-    let socket = UdpSocket::bind(format!("0.0.0.0:{}", port)).expect("Could not bind UDP socket");
-    socket
-        .set_nonblocking(false)
-        .expect("Failed to set blocking mode");
+    let udp_addrs = sockets
+        .iter()
+        .map(UdpSocket::local_addr)
+        .collect::<io::Result<Vec<_>>>()?;
+    let tcp_addrs = listeners
+        .iter()
+        .map(TcpListener::local_addr)
+        .collect::<io::Result<Vec<_>>>()?;
 
     let map = Arc::new(Map::new(config::MAP_WIDTH, config::MAP_HEIGHT));
-    let game_state: SharedGameState = Arc::new(Mutex::new(GameState::new()));
-    let clients: SharedClients = Arc::new(Mutex::new(HashMap::new()));
-    println!("Server running on port {}", port);
+    let map_chunks = Arc::new(map.chunk_map());
+    let player_count = Arc::new(AtomicU32::new(0));
+    let running = Arc::new(AtomicBool::new(true));
+    let (inputs, inputs_rx) = mpsc::sync_channel::<ServerInput>(config::INPUT_QUEUE_SIZE);
+    let (events, events_rx) = mpsc::sync_channel::<ServerEvent>(config::EVENT_QUEUE_SIZE);
+    let (shutdown, shutdown_rx) = mpsc::channel::<String>();
+    println!("Server running on port {} (tcp {})", port, tcp_port);
+    println!(
+        "Tick rate {} Hz, snapshot rate {} Hz",
+        server_config.tick_rate, server_config.snapshot_rate
+    );
 
-    let (tx, rx) = mpsc::channel::<ServerMessage>();
+    let mut threads = Vec::new();
 
-    let socket_clone = socket.try_clone().unwrap();
-    thread::spawn(move || {
-        for msg in rx {
-            let _ = send_message(&socket_clone, &msg, msg.src);
-        }
-    });
-
-    let game_state_clone = Arc::clone(&game_state);
-    let clients_clone_gs = Arc::clone(&clients);
-    let map_clone = Arc::clone(&map);
-    let tx_clone = tx.clone();
-    thread::spawn(move || {
-        let tick_rate = Duration::from_millis(16);
-        let mut last_update = Instant::now();
-        loop {
-            let tick_start = Instant::now();
-            let delta_time = (tick_start - last_update).as_secs_f32();
-            last_update = tick_start;
-            let clients_snapshot = {
-                let clients_guard = clients_clone_gs.lock().unwrap();
-                clients_guard.clone()
-            };
-            {
-                let mut game_state_lock = game_state_clone.lock().unwrap();
-                game_state_lock.update(&map_clone, delta_time);
-
-                for (&src, player_id) in &clients_snapshot {
-                    let snapshot_diff = game_state_lock.get_snapshot_diff(Some(player_id));
-                    let _ = tx_clone
-                        .send(ServerMessage {
-                            src: src,
-                            message: ServerMessageType::GameStateDiff(snapshot_diff),
-                        })
-                        .expect("failed to send to net thread");
-                }
+    if let Some(registry) = registry {
+        let heartbeat = ServerHeartbeat {
+            name: name.clone(),
+            mode: config::GAME_MODE.to_string(),
+            players: 0,
+            port,
+            tcp_port,
+        };
+        let player_count_heartbeat = Arc::clone(&player_count);
+        let running_heartbeat = Arc::clone(&running);
+        thread::spawn(move || {
+            run_registry_heartbeat(
+                registry,
+                heartbeat,
+                player_count_heartbeat,
+                running_heartbeat,
+            );
+        });
+    }
+
+    if server_config.discovery {
+        let announcement = ServerAnnouncement {
+            instance_id: thread_rng().r#gen(),
+            name: name.clone(),
+            map_width: map.width,
+            map_height: map.height,
+            players: 0,
+            port,
+            tcp_port,
+        };
+        let discovery_addr =
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, config::DISCOVERY_PORT as u16));
+        match utils::bind_udp_shared(discovery_addr) {
+            Ok(discovery_socket) => {
+                discovery_socket.set_read_timeout(Some(config::SERVER_POLL_INTERVAL))?;
+                let player_count_discovery = Arc::clone(&player_count);
+                let running_discovery = Arc::clone(&running);
+                threads.push(thread::spawn(move || {
+                    run_discovery_responder(
+                        discovery_socket,
+                        announcement,
+                        player_count_discovery,
+                        running_discovery,
+                    );
+                }));
+            }
+            Err(e) => {
+                eprintln!(
+                    "LAN discovery disabled, could not bind discovery socket: {:?}",
+                    e
+                );
             }
-            let sleep_duration = tick_rate.saturating_sub(tick_start.elapsed());
-            thread::sleep(sleep_duration);
         }
-    });
-
-    loop {
-        if let Some((msg, src)) = recv_message::<ClientMessage>(&socket) {
-            {
-                let mut clients_guard = clients.lock().unwrap();
-                if !clients_guard.contains_key(&src) {
-                    clients_guard.insert(src, 0);
-                    println!("New client: {}", src);
-                }
+    }
+
+    for listener in listeners {
+        listener.set_nonblocking(true)?;
+        let inputs_tcp = inputs.clone();
+        let map_chunks_tcp = Arc::clone(&map_chunks);
+        let running_tcp = Arc::clone(&running);
+        threads.push(thread::spawn(move || {
+            run_tcp_listener(listener, inputs_tcp, map_chunks_tcp, running_tcp);
+        }));
+    }
+
+    let receivers = sockets
+        .iter()
+        .map(UdpSocket::try_clone)
+        .collect::<io::Result<Vec<_>>>()?;
+    let (outbound, sender) = Outbound::new(sockets);
+
+    for (index, socket) in receivers.into_iter().enumerate() {
+        socket.set_read_timeout(Some(config::SERVER_POLL_INTERVAL))?;
+        let inputs_udp = inputs.clone();
+        let outbound_udp = outbound.clone();
+        let map_chunks_udp = Arc::clone(&map_chunks);
+        let running_udp = Arc::clone(&running);
+        threads.push(thread::spawn(move || {
+            run_udp_receiver(
+                socket,
+                index,
+                inputs_udp,
+                outbound_udp,
+                map_chunks_udp,
+                running_udp,
+            );
+        }));
+    }
+    drop(inputs);
+
+    let simulation = Simulation::new(&server_config, map, outbound, player_count, events);
+    let running_simulation = Arc::clone(&running);
+    threads.insert(
+        0,
+        thread::spawn(move || {
+            simulation.run(inputs_rx, shutdown_rx);
+            running_simulation.store(false, Ordering::Relaxed);
+        }),
+    );
+
+    // Joined last: it only exits after the threads above drop their queues.
+    threads.push(sender);
+
+    Ok(ServerHandle::new(
+        udp_addrs, tcp_addrs, events_rx, shutdown, threads,
+    ))
+}
+
+fn run_tcp_listener(
+    listener: TcpListener,
+    inputs: SyncSender<ServerInput>,
+    map_chunks: Arc<Vec<MapChunk>>,
+    running: Arc<AtomicBool>,
+) {
+    let handlers = Arc::new(AtomicUsize::new(0));
+    while running.load(Ordering::Relaxed) {
+        let stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(config::SERVER_POLL_INTERVAL);
+                continue;
             }
+            Err(e) => {
+                eprintln!("Failed to accept TCP connection: {:?}", e);
+                continue;
+            }
+        };
+        if handlers.load(Ordering::Relaxed) >= config::MAX_TCP_CLIENTS {
+            eprintln!(
+                "Rejected TCP connection, {} already open",
+                config::MAX_TCP_CLIENTS
+            );
+            continue;
+        }
+        let configured = stream
+            .set_nonblocking(false)
+            .and_then(|_| stream.set_read_timeout(Some(config::TCP_IO_TIMEOUT)))
+            .and_then(|_| stream.set_write_timeout(Some(config::TCP_IO_TIMEOUT)));
+        if let Err(e) = configured {
+            eprintln!("Failed to accept TCP connection: {:?}", e);
+            continue;
+        }
 
-            match msg {
-                ClientMessage::Init => {
-                    println!("Player init");
-                    let player = game_state.lock().unwrap().create_player(&map);
-                    let player_id = player.id;
-
-                    {
-                        let mut clients_lock = clients.lock().unwrap();
-                        clients_lock.insert(src, player.id);
-                    }
-                    let _ = tx
-                        .send(ServerMessage {
-                            src: src,
-                            message: ServerMessageType::InitPlayer(player),
-                        })
-                        .expect("failed to send to net thread");
-
-                    let snapshot = {
-                        let mut game_state_lock = game_state.lock().unwrap();
-                        game_state_lock.get_snapshot(Some(&player_id))
-                    };
-                    let _ = tx
-                        .send(ServerMessage {
-                            src: src,
-                            message: ServerMessageType::GameState(snapshot),
-                        })
-                        .expect("failed to send to net thread");
-                }
-                ClientMessage::Map(chunk_ids) => {
-                    let chunks = map.chunk_map();
-                    for chunk in chunks {
-                        if !chunk_ids.contains(&chunk.chunk_index) {
-                            tx.send(ServerMessage {
-                                src: src,
-                                message: ServerMessageType::Map(chunk),
-                            })
-                            .expect("failed to send to net thread");
-                        }
-                    }
-                }
-                ClientMessage::Move(direction) => {
-                    let player_id: Option<u32> = {
-                        let clients_lock = clients.lock().unwrap();
-                        clients_lock.get(&src).copied()
-                    };
-                    {
-                        let mut game_state_lock = game_state.lock().unwrap();
-                        game_state_lock.move_player(player_id.as_ref(), direction, &map);
-                    }
-                    let snapshot_diff = {
-                        let mut game_state_lock = game_state.lock().unwrap();
-                        game_state_lock.get_snapshot_diff(player_id.as_ref())
-                    };
-                    let _ = tx
-                        .send(ServerMessage {
-                            src: src,
-                            message: ServerMessageType::GameStateDiff(snapshot_diff),
-                        })
-                        .expect("failed to send to net thread");
-                }
-                ClientMessage::Shoot => {
-                    let player_id: Option<u32> = {
-                        let clients_lock = clients.lock().unwrap();
-                        clients_lock.get(&src).copied()
-                    };
-                    {
-                        let mut game_state_lock = game_state.lock().unwrap();
-                        game_state_lock.shoot(player_id.as_ref());
-                    }
-                    let snapshot_diff = {
-                        let mut game_state = game_state.lock().unwrap();
-                        game_state.get_snapshot_diff(player_id.as_ref())
-                    };
-                    let _ = tx
-                        .send(ServerMessage {
-                            src: src,
-                            message: ServerMessageType::GameStateDiff(snapshot_diff),
-                        })
-                        .expect("failed to send to net thread");
-                }
-                ClientMessage::Quit => {
-                    println!("Player disconnected {}", src);
-                    let player_id: Option<u32> = {
-                        let clients_lock = clients.lock().unwrap();
-                        clients_lock.get(&src).copied()
-                    };
-                    {
-                        let mut game_state_lock = game_state.lock().unwrap();
-                        game_state_lock.remove(player_id.as_ref());
-                    }
-                    let snapshot = {
-                        let mut game_state = game_state.lock().unwrap();
-                        game_state.get_snapshot(player_id.as_ref())
-                    };
-                    let _ = tx
-                        .send(ServerMessage {
-                            src: src,
-                            message: ServerMessageType::GameState(snapshot),
-                        })
-                        .expect("failed to send to net thread");
+        handlers.fetch_add(1, Ordering::Relaxed);
+        let handler = HandlerSlot(Arc::clone(&handlers));
+        let inputs = inputs.clone();
+        let map_chunks = Arc::clone(&map_chunks);
+        thread::spawn(move || {
+            tcp::handle_tcp_client(stream, inputs, map_chunks);
+            drop(handler);
+        });
+    }
+}
+
+/// Counts an open TCP handler until its thread finishes.
+struct HandlerSlot(Arc<AtomicUsize>);
+
+impl Drop for HandlerSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn run_udp_receiver(
+    socket: UdpSocket,
+    index: usize,
+    inputs: SyncSender<ServerInput>,
+    outbound: Outbound,
+    map_chunks: Arc<Vec<MapChunk>>,
+    running: Arc<AtomicBool>,
+) {
+    let mut reader = PacketReader::<ClientMessage>::new();
+    while running.load(Ordering::Relaxed) {
+        let Some((msg, src)) = reader.recv(&socket) else {
+            continue;
+        };
+
+        if let ClientMessage::Map(chunk_ids) = msg {
+            for chunk in map_chunks.iter() {
+                if !chunk_ids.contains(&chunk.chunk_index) {
+                    outbound.send(index, src, ServerMessageType::Map(chunk.clone()));
                 }
             }
+            continue;
         }
+
+        let input = ServerInput::Udp {
+            msg,
+            src,
+            socket: index,
+        };
+        match inputs.try_send(input) {
+            Ok(()) | Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => return,
+        }
+    }
+}
+
+fn bind_server_sockets(
+    ip: IpAddr,
+    port: u16,
+    tcp_port: u16,
+) -> io::Result<(UdpSocket, TcpListener)> {
+    let socket = utils::bind_udp(SocketAddr::new(ip, port))?;
+    socket.set_nonblocking(false)?;
+    let listener = utils::bind_tcp(SocketAddr::new(ip, tcp_port))?;
+
+    Ok((socket, listener))
+}
+
+fn run_registry_heartbeat(
+    registry: SocketAddr,
+    mut heartbeat: ServerHeartbeat,
+    player_count: Arc<AtomicU32>,
+    running: Arc<AtomicBool>,
+) {
+    let socket = match utils::bind_udp_for(registry) {
+        Ok(socket) => socket,
+        Err(e) => {
+            eprintln!("Could not bind heartbeat socket: {:?}", e);
+            return;
+        }
+    };
+    println!("Sending heartbeats to registry {}", registry);
+
+    while running.load(Ordering::Relaxed) {
+        heartbeat.players = player_count.load(Ordering::Relaxed);
+        send_heartbeat(&socket, registry, heartbeat.clone());
+        thread::sleep(config::REGISTRY_HEARTBEAT_INTERVAL);
+    }
+}
+
+fn run_discovery_responder(
+    socket: UdpSocket,
+    mut announcement: ServerAnnouncement,
+    player_count: Arc<AtomicU32>,
+    running: Arc<AtomicBool>,
+) {
+    while running.load(Ordering::Relaxed) {
+        let Some((msg, src)) = recv_message::<DiscoveryMessage>(&socket) else {
+            continue;
+        };
+        if !msg.is_valid() || !matches!(msg, DiscoveryMessage::Probe { .. }) {
+            continue;
+        }
+
+        announcement.players = player_count.load(Ordering::Relaxed);
+        send_message(
+            &socket,
+            &DiscoveryMessage::announce(announcement.clone()),
+            src,
+        );
     }
 }
@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    iter,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    config,
+    network::{
+        packet::PacketWriter,
+        state::{ServerMessage, ServerMessageType},
+    },
+};
+
+/// What the sender thread is asked to do, in order.
+enum Outgoing {
+    /// One-off replies (info, capabilities, map chunks) to any address.
+    Reply {
+        socket: usize,
+        dest: SocketAddr,
+        message: ServerMessageType,
+    },
+    Client {
+        socket: usize,
+        dest: SocketAddr,
+        message: ServerMessageType,
+        queue: Arc<QueueState>,
+    },
+    /// Answered once everything queued before it has been sent.
+    Flush(Sender<()>),
+    /// The client is gone: send what is left and drop its writer.
+    Release { socket: usize, dest: SocketAddr },
+}
+
+struct QueueState {
+    compress: AtomicBool,
+    queued: AtomicUsize,
+}
+
+/// Hands messages to a single sender thread that writes every client's
+/// datagrams. Each client queue is bounded by `CLIENT_QUEUE_SIZE` and
+/// one-off replies by `MISC_QUEUE_SIZE`, so a slow client only loses its
+/// own messages.
+#[derive(Clone)]
+pub struct Outbound {
+    tx: Sender<Outgoing>,
+    replies: Arc<AtomicUsize>,
+}
+
+pub struct ClientQueue {
+    socket: usize,
+    dest: SocketAddr,
+    tx: Sender<Outgoing>,
+    state: Arc<QueueState>,
+}
+
+impl Outbound {
+    /// The sender thread owns the sockets and exits once every `Outbound`
+    /// and `ClientQueue` is dropped.
+    pub fn new(sockets: Vec<UdpSocket>) -> (Self, JoinHandle<()>) {
+        let (tx, rx) = mpsc::channel::<Outgoing>();
+        let replies = Arc::new(AtomicUsize::new(0));
+
+        let replies_sender = Arc::clone(&replies);
+        let sender = thread::spawn(move || run_sender(sockets, rx, replies_sender));
+
+        (Self { tx, replies }, sender)
+    }
+
+    pub fn send(&self, socket: usize, dest: SocketAddr, message: ServerMessageType) -> bool {
+        if self.replies.fetch_add(1, Ordering::Relaxed) >= config::MISC_QUEUE_SIZE {
+            self.replies.fetch_sub(1, Ordering::Relaxed);
+            return false;
+        }
+        let sent = self
+            .tx
+            .send(Outgoing::Reply {
+                socket,
+                dest,
+                message,
+            })
+            .is_ok();
+        if !sent {
+            self.replies.fetch_sub(1, Ordering::Relaxed);
+        }
+        sent
+    }
+
+    pub fn client_queue(&self, socket: usize, dest: SocketAddr) -> ClientQueue {
+        ClientQueue {
+            socket,
+            dest,
+            tx: self.tx.clone(),
+            state: Arc::new(QueueState {
+                compress: AtomicBool::new(false),
+                queued: AtomicUsize::new(0),
+            }),
+        }
+    }
+}
+
+impl ClientQueue {
+    /// Whether the next `push` will be accepted. Only the simulation thread
+    /// pushes, so this stays true until it does.
+    pub fn has_room(&self) -> bool {
+        self.state.queued.load(Ordering::Relaxed) < config::CLIENT_QUEUE_SIZE
+    }
+
+    pub fn push(&self, message: ServerMessageType) -> bool {
+        if !self.has_room() {
+            return false;
+        }
+        self.state.queued.fetch_add(1, Ordering::Relaxed);
+        let sent = self
+            .tx
+            .send(Outgoing::Client {
+                socket: self.socket,
+                dest: self.dest,
+                message,
+                queue: Arc::clone(&self.state),
+            })
+            .is_ok();
+        if !sent {
+            self.state.queued.fetch_sub(1, Ordering::Relaxed);
+        }
+        sent
+    }
+
+    pub fn set_compression(&self, compress: bool) {
+        self.state.compress.store(compress, Ordering::Relaxed);
+    }
+
+    /// Closes the queue and waits until everything already pushed has been sent.
+    pub fn close(self) {
+        let (done, done_rx) = mpsc::channel();
+        if self.tx.send(Outgoing::Flush(done)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+impl Drop for ClientQueue {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Outgoing::Release {
+            socket: self.socket,
+            dest: self.dest,
+        });
+    }
+}
+
+/// Drains everything queued, coalescing messages per destination, then
+/// flushes the datagrams. Client writers are kept to reuse their buffers.
+fn run_sender(sockets: Vec<UdpSocket>, rx: Receiver<Outgoing>, replies: Arc<AtomicUsize>) {
+    let mut writers: HashMap<(usize, SocketAddr), PacketWriter> = HashMap::new();
+    let mut reply_writers: HashMap<(usize, SocketAddr), PacketWriter> = HashMap::new();
+    let mut flushed = Vec::new();
+
+    while let Ok(first) = rx.recv() {
+        for outgoing in iter::once(first).chain(rx.try_iter()) {
+            match outgoing {
+                Outgoing::Reply {
+                    socket,
+                    dest,
+                    message,
+                } => {
+                    replies.fetch_sub(1, Ordering::Relaxed);
+                    reply_writers
+                        .entry((socket, dest))
+                        .or_insert_with(|| PacketWriter::new(dest))
+                        .push(&sockets[socket], &ServerMessage { src: dest, message });
+                }
+                Outgoing::Client {
+                    socket,
+                    dest,
+                    message,
+                    queue,
+                } => {
+                    queue.queued.fetch_sub(1, Ordering::Relaxed);
+                    let writer = writers
+                        .entry((socket, dest))
+                        .or_insert_with(|| PacketWriter::new(dest));
+                    writer.set_compression(queue.compress.load(Ordering::Relaxed));
+                    writer.push(&sockets[socket], &ServerMessage { src: dest, message });
+                }
+                Outgoing::Flush(done) => flushed.push(done),
+                Outgoing::Release { socket, dest } => {
+                    if let Some(mut writer) = writers.remove(&(socket, dest)) {
+                        writer.flush(&sockets[socket]);
+                    }
+                }
+            }
+        }
+
+        for ((socket, _), writer) in writers.iter_mut() {
+            writer.flush(&sockets[*socket]);
+        }
+        for ((socket, _), writer) in reply_writers.iter_mut() {
+            writer.flush(&sockets[*socket]);
+        }
+        reply_writers.clear();
+        for done in flushed.drain(..) {
+            let _ = done.send(());
+        }
+    }
+}
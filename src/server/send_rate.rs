@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+#[derive(Debug)]
+pub struct SendRate {
+    pub rate: f32,
+    pub loss: f32,
+    max_rate: f32,
+    next_send: Instant,
+    last_report: Option<(Instant, u32)>,
+}
+
+impl SendRate {
+    pub fn new(max_rate: f32) -> Self {
+        Self {
+            rate: max_rate,
+            loss: 0.0,
+            max_rate,
+            next_send: Instant::now(),
+            last_report: None,
+        }
+    }
+
+    pub fn due(&mut self, now: Instant) -> bool {
+        if now < self.next_send {
+            return false;
+        }
+
+        self.next_send += Duration::from_secs_f32(1.0 / self.rate);
+        if self.next_send < now {
+            self.next_send = now;
+        }
+        true
+    }
+
+    pub fn on_report(&mut self, last_seq: u32, received: u32, now: Instant) {
+        if let Some((prev_time, prev_seq)) = self.last_report {
+            let sent = last_seq.wrapping_sub(prev_seq);
+            if sent == 0 {
+                return;
+            }
+
+            self.loss = 1.0 - (received as f32 / sent as f32).min(1.0);
+            if self.loss > config::SNAPSHOT_LOSS_THRESHOLD {
+                let elapsed = (now - prev_time).as_secs_f32().max(0.001);
+                let delivered = received as f32 / elapsed;
+                self.decrease(delivered);
+            } else {
+                self.rate = (self.rate + config::SNAPSHOT_RATE_INCREASE).min(self.max_rate);
+            }
+        }
+
+        self.last_report = Some((now, last_seq));
+    }
+
+    pub fn on_backlog(&mut self) {
+        self.decrease(self.rate);
+    }
+
+    fn decrease(&mut self, delivered: f32) {
+        self.rate = (self.rate * config::SNAPSHOT_RATE_DECREASE)
+            .min(delivered)
+            .clamp(config::MIN_SNAPSHOT_RATE.min(self.max_rate), self.max_rate);
+    }
+}
@@ -0,0 +1,710 @@
+use ::rand::{Rng, thread_rng};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+        mpsc::{Receiver, RecvTimeoutError, Sender, SyncSender},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config,
+    game::{event::GameEvent, player::Player, state::GameState},
+    map::Map,
+    network::{
+        reliable::ReliableSender,
+        state::{
+            AdminCommand, Capabilities, ChatMessage, ClientMessage, PlayerScore, ScoreboardEntry,
+            ServerInfo, ServerMessageType, sanitize_chat,
+        },
+    },
+};
+
+use super::{
+    ServerConfig, ServerEvent,
+    outbound::{ClientQueue, Outbound},
+    send_rate::SendRate,
+};
+
+pub enum ServerInput {
+    Udp {
+        msg: ClientMessage,
+        src: SocketAddr,
+        socket: usize,
+    },
+    Join {
+        name: String,
+        reply: Sender<(Player, u64)>,
+    },
+    Admin {
+        command: AdminCommand,
+        reply: Sender<String>,
+    },
+}
+
+struct ClientSlot {
+    player_id: u32,
+    queue: ClientQueue,
+    send_rate: SendRate,
+    seq: u32,
+    desyncs: u32,
+    ping_ms: Option<u32>,
+    pending_ping: Option<(u32, Instant)>,
+    events: ReliableSender<GameEvent>,
+    chat: ReliableSender<ChatMessage>,
+    /// When the last chat messages were accepted, for the rate limit.
+    chat_sent: VecDeque<Instant>,
+    /// When anything last arrived from this address.
+    last_heard: Instant,
+}
+
+pub struct Simulation {
+    game_state: GameState,
+    map: Arc<Map>,
+    clients: HashMap<SocketAddr, ClientSlot>,
+    join_tokens: HashMap<u64, u32>,
+    /// Players joined over TCP that have not bound a UDP address yet.
+    pending_joins: HashMap<u32, Instant>,
+    capabilities: Capabilities,
+    /// Agreed capabilities of addresses that said Hello but have not joined
+    /// yet, with when they did. Bounded and expired, since anyone can send one.
+    peer_capabilities: HashMap<SocketAddr, (Capabilities, Instant)>,
+    outbound: Outbound,
+    player_count: Arc<AtomicU32>,
+    events: SyncSender<ServerEvent>,
+    name: String,
+    started_at: Instant,
+    desyncs: u64,
+    tick_interval: Duration,
+    snapshot_rate: f32,
+    tick: u64,
+    ping_nonce: u32,
+    next_ping: Instant,
+    next_scoreboard: Instant,
+}
+
+impl Simulation {
+    pub fn new(
+        server_config: &ServerConfig,
+        map: Arc<Map>,
+        outbound: Outbound,
+        player_count: Arc<AtomicU32>,
+        events: SyncSender<ServerEvent>,
+    ) -> Self {
+        let tick_rate = server_config.tick_rate.max(1);
+        let snapshot_rate = server_config.snapshot_rate.clamp(1, tick_rate);
+
+        Self {
+            game_state: GameState::new(),
+            map,
+            clients: HashMap::new(),
+            join_tokens: HashMap::new(),
+            pending_joins: HashMap::new(),
+            capabilities: Capabilities {
+                compression: server_config.compression,
+            },
+            peer_capabilities: HashMap::new(),
+            outbound,
+            player_count,
+            events,
+            name: server_config.name.clone(),
+            started_at: Instant::now(),
+            desyncs: 0,
+            tick_interval: Duration::from_secs_f32(1.0 / tick_rate as f32),
+            snapshot_rate: snapshot_rate as f32,
+            tick: 0,
+            ping_nonce: 0,
+            next_ping: Instant::now(),
+            next_scoreboard: Instant::now(),
+        }
+    }
+
+    pub fn run(mut self, inputs: Receiver<ServerInput>, shutdown: Receiver<String>) {
+        let mut last_update = Instant::now();
+        let mut next_tick = last_update + self.tick_interval;
+
+        loop {
+            if let Ok(reason) = shutdown.try_recv() {
+                self.shutdown(&reason);
+                return;
+            }
+
+            let timeout = next_tick.saturating_duration_since(Instant::now());
+            match inputs.recv_timeout(timeout) {
+                Ok(input) => self.handle_input(input),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+
+            let now = Instant::now();
+            if now < next_tick {
+                continue;
+            }
+
+            let delta_time = (now - last_update).as_secs_f32();
+            last_update = now;
+            self.tick(delta_time, now);
+
+            next_tick += self.tick_interval;
+            if next_tick < now {
+                next_tick = now + self.tick_interval;
+            }
+        }
+    }
+
+    fn tick(&mut self, delta_time: f32, now: Instant) {
+        self.tick += 1;
+        self.expire_joins(now);
+        self.expire_silent(now);
+        self.peer_capabilities
+            .retain(|_, (_, at)| now.duration_since(*at) < config::HELLO_TTL);
+        self.game_state.update(&self.map, delta_time);
+        for event in self.game_state.take_events() {
+            if let GameEvent::Kill { killer, victim } = event {
+                println!(
+                    "{} killed {}",
+                    self.game_state.player_name(killer),
+                    self.game_state.player_name(victim)
+                );
+                self.emit(ServerEvent::PlayerKilled { killer, victim });
+            }
+            for slot in self.clients.values_mut() {
+                if event.concerns(slot.player_id) {
+                    slot.events.push(event.clone());
+                }
+            }
+        }
+
+        for slot in self.clients.values_mut() {
+            if let Some((first_seq, events)) = slot.events.due(now, config::EVENT_PAGE_SIZE) {
+                slot.queue
+                    .push(ServerMessageType::Events { first_seq, events });
+            }
+            if let Some((first_seq, messages)) = slot.chat.due(now, config::CHAT_PAGE_SIZE) {
+                slot.queue.push(ServerMessageType::Chat {
+                    first_seq,
+                    messages,
+                });
+            }
+            if !slot.send_rate.due(now) {
+                continue;
+            }
+            // Building a diff advances `prev_states`, so a diff that cannot be
+            // queued would leave the client out of step until the next resync.
+            if !slot.queue.has_room() {
+                slot.send_rate.on_backlog();
+                continue;
+            }
+
+            let mut snapshot_diff = self.game_state.get_snapshot_diff(Some(&slot.player_id));
+            slot.seq = slot.seq.wrapping_add(1);
+            snapshot_diff.seq = slot.seq;
+            snapshot_diff.tick = self.tick;
+            if slot.seq % config::CHECKSUM_INTERVAL == 0 {
+                snapshot_diff.checksum = self.game_state.relevant_checksum(slot.player_id);
+            }
+            slot.queue
+                .push(ServerMessageType::GameStateDiff(snapshot_diff));
+        }
+
+        if now >= self.next_ping {
+            self.next_ping = now + config::PING_INTERVAL;
+            self.ping_nonce = self.ping_nonce.wrapping_add(1);
+            for slot in self.clients.values_mut() {
+                slot.pending_ping = Some((self.ping_nonce, now));
+                slot.queue.push(ServerMessageType::Ping(self.ping_nonce));
+            }
+        }
+        if now >= self.next_scoreboard {
+            self.next_scoreboard = now + config::SCOREBOARD_INTERVAL;
+            self.send_scoreboard();
+        }
+    }
+
+    /// Sends every player's score to every client, split into pages that fit
+    /// a datagram. Clients only see nearby players in the game state.
+    /// Each player's client slot, built once instead of a search per player.
+    fn slots_by_player(&self) -> HashMap<u32, &ClientSlot> {
+        self.clients
+            .values()
+            .map(|slot| (slot.player_id, slot))
+            .collect()
+    }
+
+    fn send_scoreboard(&self) {
+        let slots = self.slots_by_player();
+        let entries: Vec<ScoreboardEntry> = self
+            .game_state
+            .players
+            .values()
+            .map(|p| ScoreboardEntry {
+                id: p.id,
+                name: p.name.clone(),
+                kills: p.kills,
+                deths: p.deths,
+                ping_ms: slots.get(&p.id).and_then(|slot| slot.ping_ms),
+                bullet_damage: p.bullet_damage,
+                fire_rate: p.fire_rate,
+                walk_speed: p.walk_speed,
+            })
+            .collect();
+
+        let total = entries.len() as u32;
+        for page in entries.chunks(config::SCOREBOARD_PAGE_SIZE) {
+            for slot in self.clients.values() {
+                slot.queue.push(ServerMessageType::Scoreboard {
+                    total,
+                    entries: page.to_vec(),
+                });
+            }
+        }
+    }
+
+    /// Tells every client why the server is going away and waits for the
+    /// notices to leave the send queues. Inputs are no longer read after this,
+    /// so no new players can join.
+    fn shutdown(&mut self, reason: &str) {
+        println!("Shutting down: {}", reason);
+
+        for round in 0..config::SHUTDOWN_NOTICE_REPEATS {
+            if round > 0 {
+                thread::sleep(config::SHUTDOWN_NOTICE_INTERVAL);
+            }
+            for slot in self.clients.values() {
+                slot.queue
+                    .push(ServerMessageType::Shutdown(reason.to_string()));
+            }
+        }
+
+        for (_, slot) in self.clients.drain() {
+            slot.queue.close();
+        }
+        self.player_count.store(0, Ordering::Relaxed);
+    }
+
+    fn handle_input(&mut self, input: ServerInput) {
+        match input {
+            ServerInput::Udp { msg, src, socket } => self.handle_message(msg, src, socket),
+            ServerInput::Join { name, reply } => {
+                let player = self.game_state.create_player(&self.map, &name);
+                println!("{} joined as player {}", player.name, player.id);
+                let token = self.token_for(player.id);
+                self.pending_joins.insert(player.id, Instant::now());
+                self.emit(ServerEvent::PlayerJoined { id: player.id });
+                let _ = reply.send((player, token));
+            }
+            ServerInput::Admin { command, reply } => {
+                let response = self.run_admin_command(command);
+                let _ = reply.send(response);
+            }
+        }
+
+        self.player_count
+            .store(self.game_state.players.len() as u32, Ordering::Relaxed);
+    }
+
+    fn handle_message(&mut self, msg: ClientMessage, src: SocketAddr, socket: usize) {
+        let player_id = self.clients.get_mut(&src).map(|slot| {
+            slot.last_heard = Instant::now();
+            slot.player_id
+        });
+
+        match msg {
+            ClientMessage::Query => {
+                self.outbound
+                    .send(socket, src, ServerMessageType::Info(self.server_info()));
+            }
+            ClientMessage::Init(name) => {
+                let id = match player_id {
+                    // A repeated Init whose reply was lost: resend the same player.
+                    Some(id) => id,
+                    None => {
+                        println!("New client: {}", src);
+                        let player = self.game_state.create_player(&self.map, &name);
+                        println!("{} joined as player {}", player.name, player.id);
+                        self.add_client(src, socket, player.id);
+                        self.emit(ServerEvent::PlayerJoined { id: player.id });
+                        player.id
+                    }
+                };
+
+                let token = self.token_for(id);
+                if let Some(player) = self.game_state.players.get(&id).cloned()
+                    && let Some(slot) = self.clients.get(&src)
+                {
+                    slot.queue.push(ServerMessageType::InitPlayer {
+                        player: Box::new(player),
+                        token,
+                    });
+                }
+                self.send_snapshot(src, id);
+            }
+            ClientMessage::Bind(token) => {
+                let Some(&player_id) = self.join_tokens.get(&token) else {
+                    eprintln!("Unknown join token from {}", src);
+                    return;
+                };
+
+                if !self.clients.contains_key(&src) {
+                    // A reconnecting client binds from a new socket: its old
+                    // address stops receiving and the player moves over.
+                    if let Some(old) = self
+                        .clients
+                        .iter()
+                        .find(|(_, slot)| slot.player_id == player_id)
+                        .map(|(addr, _)| *addr)
+                    {
+                        println!(
+                            "Player {} moved from {} to {}",
+                            self.game_state.player_name(player_id),
+                            old,
+                            src
+                        );
+                        self.clients.remove(&old);
+                    } else {
+                        println!("New client: {}", src);
+                    }
+                    self.add_client(src, socket, player_id);
+                }
+                self.pending_joins.remove(&player_id);
+                self.send_snapshot(src, player_id);
+            }
+            ClientMessage::Move(direction) => {
+                if player_id.is_some() {
+                    self.game_state
+                        .move_player(player_id.as_ref(), direction, &self.map);
+                }
+            }
+            ClientMessage::Shoot => {
+                if player_id.is_some() {
+                    self.game_state.shoot(player_id.as_ref());
+                }
+            }
+            ClientMessage::Quit => {
+                let Some(id) = player_id else {
+                    return;
+                };
+                println!(
+                    "Player {} disconnected {}",
+                    self.game_state.player_name(id),
+                    src
+                );
+                self.peer_capabilities.remove(&src);
+                self.remove_player(id);
+            }
+            ClientMessage::NetStats { last_seq, received } => {
+                if let Some(slot) = self.clients.get_mut(&src) {
+                    slot.send_rate.on_report(last_seq, received, Instant::now());
+                }
+            }
+            ClientMessage::Hello(client_capabilities) => {
+                let agreed = self.capabilities.negotiate(&client_capabilities);
+                match self.clients.get(&src) {
+                    Some(slot) => slot.queue.set_compression(agreed.compression),
+                    None => {
+                        if self.peer_capabilities.len() < config::MAX_PENDING_HELLOS
+                            || self.peer_capabilities.contains_key(&src)
+                        {
+                            self.peer_capabilities.insert(src, (agreed, Instant::now()));
+                        }
+                    }
+                }
+                self.outbound
+                    .send(socket, src, ServerMessageType::Hello(agreed));
+            }
+            ClientMessage::Resync => {
+                let Some(id) = player_id else {
+                    return;
+                };
+                if let Some(slot) = self.clients.get_mut(&src) {
+                    slot.desyncs += 1;
+                }
+                self.desyncs += 1;
+                self.send_snapshot(src, id);
+            }
+            ClientMessage::Viewport { half_w, half_h } => {
+                if let Some(id) = player_id {
+                    self.game_state.set_viewport(id, half_w, half_h);
+                }
+            }
+            ClientMessage::Pong(nonce) => {
+                if let Some(slot) = self.clients.get_mut(&src)
+                    && let Some((sent_nonce, sent_at)) = slot.pending_ping
+                    && sent_nonce == nonce
+                {
+                    let rtt = sent_at.elapsed().as_millis() as u32;
+                    slot.ping_ms = Some(slot.ping_ms.map_or(rtt, |ping| (ping * 3 + rtt) / 4));
+                    slot.pending_ping = None;
+                }
+            }
+            ClientMessage::EventAck(next_expected) => {
+                if let Some(slot) = self.clients.get_mut(&src) {
+                    slot.events.ack(next_expected);
+                }
+            }
+            ClientMessage::Chat(text) => {
+                if let Some(id) = player_id {
+                    self.handle_chat(src, id, &text);
+                }
+            }
+            ClientMessage::ChatAck(next_expected) => {
+                if let Some(slot) = self.clients.get_mut(&src) {
+                    slot.chat.ack(next_expected);
+                }
+            }
+            ClientMessage::Map(_) | ClientMessage::Admin(_) => {}
+        }
+    }
+
+    /// Broadcasts a chat line from `player_id` unless it is empty or the
+    /// sender is over `CHAT_RATE_LIMIT`, in which case only they get a notice.
+    fn handle_chat(&mut self, src: SocketAddr, player_id: u32, text: &str) {
+        let Some(text) = sanitize_chat(text) else {
+            return;
+        };
+        let Some(slot) = self.clients.get_mut(&src) else {
+            return;
+        };
+
+        if !allow_chat(&mut slot.chat_sent, Instant::now()) {
+            slot.chat.push(ChatMessage {
+                sender: None,
+                name: "server".to_string(),
+                text: "You are sending messages too fast".to_string(),
+                timestamp: unix_time(),
+            });
+            return;
+        }
+
+        let name = self.game_state.player_name(player_id);
+        println!("[chat] {}: {}", name, text);
+        let message = ChatMessage {
+            sender: Some(player_id),
+            name,
+            text,
+            timestamp: unix_time(),
+        };
+        for slot in self.clients.values_mut() {
+            slot.chat.push(message.clone());
+        }
+        self.emit(ServerEvent::ChatMessage {
+            id: player_id,
+            text: message.text,
+        });
+    }
+
+    /// The token that lets a client bind, or later rebind, to `player_id`.
+    fn token_for(&mut self, player_id: u32) -> u64 {
+        if let Some((&token, _)) = self.join_tokens.iter().find(|(_, id)| **id == player_id) {
+            return token;
+        }
+        let token = thread_rng().r#gen::<u64>();
+        self.join_tokens.insert(token, player_id);
+        token
+    }
+
+    /// Drops players that joined over TCP but never bound within
+    /// `JOIN_BIND_TIMEOUT`, so abandoned joins do not stay in the arena.
+    fn expire_joins(&mut self, now: Instant) {
+        let expired: Vec<u32> = self
+            .pending_joins
+            .iter()
+            .filter(|(_, joined)| now.duration_since(**joined) >= config::JOIN_BIND_TIMEOUT)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            println!(
+                "Player {} never bound, removing",
+                self.game_state.player_name(id)
+            );
+            self.remove_player(id);
+        }
+    }
+
+    /// Removes players whose client has sent nothing for `CLIENT_TIMEOUT`,
+    /// e.g. after a crash or a lost connection that never reconnected.
+    fn expire_silent(&mut self, now: Instant) {
+        let silent: Vec<(SocketAddr, u32)> = self
+            .clients
+            .iter()
+            .filter(|(_, slot)| now.duration_since(slot.last_heard) >= config::CLIENT_TIMEOUT)
+            .map(|(addr, slot)| (*addr, slot.player_id))
+            .collect();
+        for (addr, id) in silent {
+            println!(
+                "Player {} timed out {}",
+                self.game_state.player_name(id),
+                addr
+            );
+            self.remove_player(id);
+        }
+    }
+
+    /// Removes `id` from the game together with its client slots and tokens.
+    fn remove_player(&mut self, id: u32) {
+        self.game_state.remove(Some(&id));
+        self.clients.retain(|_, slot| slot.player_id != id);
+        self.join_tokens.retain(|_, pid| *pid != id);
+        self.pending_joins.remove(&id);
+        self.emit(ServerEvent::PlayerLeft { id });
+        self.player_count
+            .store(self.game_state.players.len() as u32, Ordering::Relaxed);
+    }
+
+    fn emit(&self, event: ServerEvent) {
+        let _ = self.events.try_send(event);
+    }
+
+    fn add_client(&mut self, src: SocketAddr, socket: usize, player_id: u32) {
+        let queue = self.outbound.client_queue(socket, src);
+        let agreed = self
+            .peer_capabilities
+            .remove(&src)
+            .map(|(agreed, _)| agreed)
+            .unwrap_or_default();
+        queue.set_compression(agreed.compression);
+        let send_rate = SendRate::new(self.snapshot_rate);
+        self.clients.insert(
+            src,
+            ClientSlot {
+                player_id,
+                queue,
+                send_rate,
+                seq: 0,
+                desyncs: 0,
+                ping_ms: None,
+                pending_ping: None,
+                events: ReliableSender::new(
+                    config::MAX_PENDING_EVENTS,
+                    config::EVENT_RESEND_INTERVAL,
+                ),
+                chat: ReliableSender::new(config::MAX_PENDING_CHAT, config::CHAT_RESEND_INTERVAL),
+                chat_sent: VecDeque::new(),
+                last_heard: Instant::now(),
+            },
+        );
+    }
+
+    fn send_snapshot(&mut self, src: SocketAddr, player_id: u32) {
+        let snapshot = self.game_state.get_snapshot(Some(&player_id));
+        if let Some(slot) = self.clients.get(&src) {
+            slot.queue
+                .push(ServerMessageType::GameState(Box::new(snapshot)));
+        }
+    }
+
+    fn server_info(&self) -> ServerInfo {
+        let mut players: Vec<PlayerScore> = self
+            .game_state
+            .players
+            .values()
+            .map(|p| PlayerScore {
+                id: p.id,
+                name: p.name.clone(),
+                kills: p.kills,
+                deths: p.deths,
+            })
+            .collect();
+        players.sort_by_key(|p| p.id);
+
+        ServerInfo {
+            name: self.name.clone(),
+            mode: config::GAME_MODE.to_string(),
+            map_width: self.map.width,
+            map_height: self.map.height,
+            players,
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            desyncs: self.desyncs,
+        }
+    }
+
+    fn run_admin_command(&mut self, command: AdminCommand) -> String {
+        match command {
+            AdminCommand::Players => {
+                let mut players: Vec<_> = self.game_state.players.values().collect();
+                players.sort_by_key(|p| p.id);
+
+                let mut lines = vec![format!(
+                    "Players online: {} | Desyncs: {}",
+                    players.len(),
+                    self.desyncs
+                )];
+                let slots = self.slots_by_player();
+                for player in players {
+                    let slot = slots.get(&player.id);
+                    let desyncs = slot.map_or(0, |slot| slot.desyncs);
+                    let ping = slot
+                        .and_then(|slot| slot.ping_ms)
+                        .map_or("-".to_string(), |ping| format!("{}ms", ping));
+                    lines.push(format!(
+                        "ID: {} | Name: {} | Kills: {} | Deths: {} | Health {}/{} | Ping: {} | Desyncs: {}",
+                        player.id,
+                        player.name,
+                        player.kills,
+                        player.deths,
+                        player.health,
+                        player.max_health,
+                        ping,
+                        desyncs,
+                    ));
+                }
+                lines.join("\n")
+            }
+            AdminCommand::Kick(id) => {
+                if !self.game_state.players.contains_key(&id) {
+                    return format!("No player with id {}", id);
+                }
+                let name = self.game_state.player_name(id);
+                self.remove_player(id);
+                println!("Player {} ({}) kicked", id, name);
+                format!("Kicked player {} ({})", id, name)
+            }
+        }
+    }
+}
+
+/// At most `CHAT_RATE_LIMIT` messages per `CHAT_RATE_WINDOW`; records `now`
+/// when the message is allowed.
+fn allow_chat(sent: &mut VecDeque<Instant>, now: Instant) -> bool {
+    while sent
+        .front()
+        .is_some_and(|at| now.duration_since(*at) >= config::CHAT_RATE_WINDOW)
+    {
+        sent.pop_front();
+    }
+    if sent.len() >= config::CHAT_RATE_LIMIT {
+        return false;
+    }
+    sent.push_back(now);
+    true
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_within_the_rate_window_is_rejected() {
+        let start = Instant::now();
+        let mut sent = VecDeque::new();
+        for _ in 0..config::CHAT_RATE_LIMIT {
+            assert!(allow_chat(&mut sent, start));
+        }
+        let soon = start + config::CHAT_RATE_WINDOW / 2;
+        assert!(!allow_chat(&mut sent, soon));
+        assert_eq!(sent.len(), config::CHAT_RATE_LIMIT);
+
+        assert!(allow_chat(&mut sent, start + config::CHAT_RATE_WINDOW));
+        assert_eq!(sent.len(), 1);
+    }
+}
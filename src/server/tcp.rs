@@ -0,0 +1,75 @@
+use std::{
+    net::TcpStream,
+    sync::{
+        Arc,
+        mpsc::{self, SyncSender},
+    },
+};
+
+use crate::network::{
+    recv_tcp_message, send_tcp_message,
+    state::{ClientMessage, MapChunk, ServerMessageType},
+};
+
+use super::simulation::ServerInput;
+
+pub fn handle_tcp_client(
+    mut stream: TcpStream,
+    inputs: SyncSender<ServerInput>,
+    map_chunks: Arc<Vec<MapChunk>>,
+) {
+    let Ok(peer) = stream.peer_addr() else {
+        return;
+    };
+
+    while let Some(msg) = recv_tcp_message::<ClientMessage>(&mut stream) {
+        match msg {
+            ClientMessage::Init(name) => {
+                println!("Player init over TCP {}", peer);
+                let (reply, reply_rx) = mpsc::channel();
+                if inputs.send(ServerInput::Join { name, reply }).is_err() {
+                    break;
+                }
+                let Ok((player, token)) = reply_rx.recv() else {
+                    break;
+                };
+
+                let welcome = ServerMessageType::Welcome {
+                    player: Box::new(player),
+                    token,
+                };
+                if !send_tcp_message(&mut stream, &welcome) {
+                    break;
+                }
+            }
+            ClientMessage::Map(chunk_ids) => {
+                for chunk in map_chunks.iter() {
+                    if chunk_ids.contains(&chunk.chunk_index) {
+                        continue;
+                    }
+                    if !send_tcp_message(&mut stream, &ServerMessageType::Map(chunk.clone())) {
+                        return;
+                    }
+                }
+            }
+            ClientMessage::Admin(command) => {
+                let response = if peer.ip().to_canonical().is_loopback() {
+                    let (reply, reply_rx) = mpsc::channel();
+                    if inputs.send(ServerInput::Admin { command, reply }).is_err() {
+                        break;
+                    }
+                    reply_rx.recv().unwrap_or_default()
+                } else {
+                    "Admin commands are only accepted from localhost".to_string()
+                };
+
+                if !send_tcp_message(&mut stream, &ServerMessageType::Admin(response)) {
+                    break;
+                }
+            }
+            other => {
+                eprintln!("Unexpected TCP message from {}: {:?}", peer, other);
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::{config, game::state::Direction};
+
+/// Keys currently held down. Without release events a key counts as held
+/// until `TUI_KEY_HOLD` passes without a press or auto-repeat.
+///
+/// Enter or `t` opens the chat line; while it is open keys are typed into it
+/// instead of moving, Enter sends and Esc closes it. `q` or Ctrl-C quits; a
+/// stray Esc does not, so closing the chat line never leaves the game.
+pub struct Input {
+    held: HashMap<KeyCode, Instant>,
+    release_events: bool,
+    quit: bool,
+    resized: bool,
+    chat: Option<String>,
+    chat_sent: Option<String>,
+    debug: bool,
+}
+
+impl Input {
+    pub fn new(release_events: bool) -> Self {
+        Self {
+            held: HashMap::new(),
+            release_events,
+            quit: false,
+            resized: false,
+            chat: None,
+            chat_sent: None,
+            debug: false,
+        }
+    }
+
+    /// Reads terminal events until `timeout` passes.
+    pub fn read(&mut self, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if !event::poll(left)? {
+                break;
+            }
+            match event::read()? {
+                Event::Key(key) => self.on_key(key),
+                Event::Resize(_, _) => self.resized = true,
+                _ => {}
+            }
+        }
+
+        if !self.release_events {
+            self.held
+                .retain(|_, pressed| pressed.elapsed() < config::TUI_KEY_HOLD);
+        }
+        Ok(())
+    }
+
+    fn on_key(&mut self, key: KeyEvent) {
+        let code = match key.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        match key.kind {
+            KeyEventKind::Release => {
+                self.held.remove(&code);
+            }
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                let ctrl_c =
+                    code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if ctrl_c {
+                    self.quit = true;
+                } else if self.chat.is_some() {
+                    self.on_chat_key(key.code);
+                } else if code == KeyCode::Enter || code == KeyCode::Char('t') {
+                    self.chat = Some(String::new());
+                    self.held.clear();
+                } else if code == KeyCode::F(3) {
+                    if key.kind == KeyEventKind::Press {
+                        self.debug = !self.debug;
+                    }
+                } else {
+                    if code == KeyCode::Char('q') {
+                        self.quit = true;
+                    }
+                    self.held.insert(code, Instant::now());
+                }
+            }
+        }
+    }
+
+    fn on_chat_key(&mut self, code: KeyCode) {
+        let Some(text) = self.chat.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => {
+                let text = text.trim().to_string();
+                if !text.is_empty() {
+                    self.chat_sent = Some(text);
+                }
+                self.chat = None;
+            }
+            KeyCode::Esc => self.chat = None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) if text.chars().count() < config::MAX_CHAT_LEN => text.push(c),
+            _ => {}
+        }
+    }
+
+    /// F3 toggles the debug panel.
+    pub fn debug(&self) -> bool {
+        self.debug
+    }
+
+    /// The chat line being typed, if open.
+    pub fn chat(&self) -> Option<&str> {
+        self.chat.as_deref()
+    }
+
+    /// A chat line once after Enter was pressed on it.
+    pub fn take_chat_sent(&mut self) -> Option<String> {
+        self.chat_sent.take()
+    }
+
+    fn is_down(&self, code: KeyCode) -> bool {
+        self.held.contains_key(&code)
+    }
+
+    pub fn movement(&self) -> Option<Direction> {
+        let pressed = |keys: [KeyCode; 3]| keys.iter().any(|&key| self.is_down(key));
+        let up = pressed([KeyCode::Char('w'), KeyCode::Up, KeyCode::Char('k')]);
+        let down = pressed([KeyCode::Char('s'), KeyCode::Down, KeyCode::Char('j')]);
+        let left = pressed([KeyCode::Char('a'), KeyCode::Left, KeyCode::Char('h')]);
+        let right = pressed([KeyCode::Char('d'), KeyCode::Right, KeyCode::Char('l')]);
+
+        if up {
+            Some(Direction::Up)
+        } else if down {
+            Some(Direction::Down)
+        } else if left {
+            Some(Direction::Left)
+        } else if right {
+            Some(Direction::Right)
+        } else {
+            None
+        }
+    }
+
+    pub fn shoot(&self) -> bool {
+        self.is_down(KeyCode::Char(' '))
+    }
+
+    pub fn scoreboard(&self) -> bool {
+        self.is_down(KeyCode::Tab)
+    }
+
+    pub fn quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Leaves a final screen: quit keys or Esc.
+    pub fn dismiss(&self) -> bool {
+        self.quit || self.is_down(KeyCode::Esc)
+    }
+
+    /// True once after the terminal was resized.
+    pub fn take_resized(&mut self) -> bool {
+        std::mem::take(&mut self.resized)
+    }
+}
@@ -0,0 +1,4 @@
+pub mod input;
+pub mod render;
+pub mod screen;
+pub mod terminal;
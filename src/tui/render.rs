@@ -0,0 +1,482 @@
+use std::time::Duration;
+
+use crossterm::style::Color;
+
+use crate::{
+    client::state::{ChatEntry, ClientState, FloatingText, FloatingTextKind, KillFeedEntry},
+    config,
+    game::{
+        modifier::ModifierKind,
+        player::Player,
+        state::{Direction, GameState},
+    },
+    map::{Map, Tile},
+    network::state::ScoreboardEntry,
+};
+
+use super::screen::Screen;
+
+const FLOOR: Color = Color::Grey;
+const WALL: Color = Color::DarkYellow;
+const OUTSIDE: Color = Color::Black;
+
+/// Maps world coordinates to screen cells, centred on the local player.
+/// The arena spans every row except the HUD line and the help line.
+struct View {
+    center_x: f32,
+    center_y: f32,
+    origin: (f32, f32),
+    columns: f32,
+}
+
+impl View {
+    fn new(screen: &Screen, origin: (f32, f32)) -> Self {
+        Self {
+            center_x: (screen.width() / 2) as f32,
+            center_y: (1 + screen.height().saturating_sub(2) / 2) as f32,
+            origin,
+            columns: config::TUI_TILE_COLUMNS as f32,
+        }
+    }
+
+    fn cell(&self, x: f32, y: f32) -> (i32, i32) {
+        (
+            (self.center_x + ((x - self.origin.0) * self.columns).floor()) as i32,
+            (self.center_y + (y - self.origin.1).floor()) as i32,
+        )
+    }
+
+    fn tile(&self, col: i32, row: i32) -> (f32, f32) {
+        (
+            (self.origin.0 + (col as f32 - self.center_x) / self.columns).floor(),
+            (self.origin.1 + row as f32 - self.center_y).floor(),
+        )
+    }
+}
+
+/// Half extent of the visible arena in tiles, for `Connection::set_viewport`.
+pub fn visible_half_extent(width: u16, height: u16) -> (f32, f32) {
+    (
+        width as f32 / config::TUI_TILE_COLUMNS as f32 / 2.0 + config::VIEW_MARGIN,
+        height as f32 / 2.0 + config::VIEW_MARGIN,
+    )
+}
+
+pub fn draw_game(screen: &mut Screen, map: &Map, game_state: &GameState, current: &Player) {
+    let view = View::new(screen, (current.x, current.y));
+    let arena_bottom = screen.height() as i32 - 1;
+
+    for row in 1..arena_bottom {
+        for col in 0..screen.width() as i32 {
+            let (x, y) = view.tile(col, row);
+            let bg = match tile_at(map, x, y) {
+                Some(Tile::Empty) => FLOOR,
+                Some(Tile::Wall) => WALL,
+                None => OUTSIDE,
+            };
+            screen.set(col, row, ' ', Color::Reset, bg);
+        }
+    }
+
+    let width = screen.width() as i32;
+    let in_arena =
+        |(col, row): (i32, i32)| row >= 1 && row < arena_bottom && col >= 0 && col < width;
+
+    for modifier in game_state.modifieres.values() {
+        let (col, row) = view.cell(modifier.x, modifier.y);
+        let color = match modifier.kind {
+            ModifierKind::Heal(_) => Color::Red,
+            ModifierKind::Damage(_) => Color::Magenta,
+            ModifierKind::Speed(_) => Color::Green,
+            ModifierKind::FireRate(_) => Color::DarkGreen,
+            ModifierKind::BulletRange(_) => Color::Cyan,
+        };
+        screen.put(col, row, '+', color);
+    }
+
+    for bullet in game_state.bullets.values() {
+        let (col, row) = view.cell(bullet.x, bullet.y);
+        screen.put(col, row, '•', Color::DarkMagenta);
+    }
+
+    for player in game_state.players.values() {
+        if !player.to_render {
+            continue;
+        }
+        let cell = view.cell(player.x, player.y);
+        if in_arena(cell) {
+            draw_player(screen, player, cell, player.id == current.id);
+        } else {
+            draw_offscreen_arrow(screen, &view, player.x - current.x, player.y - current.y);
+        }
+    }
+
+    draw_hud(screen, current);
+    screen.text(
+        0,
+        arena_bottom,
+        "WASD/arrows/HJKL move  Space shoot  Tab scores  Enter chat  Q quit",
+        Color::DarkGrey,
+    );
+}
+
+fn tile_at(map: &Map, x: f32, y: f32) -> Option<&Tile> {
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+    map.tiles.get(y as usize)?.get(x as usize)
+}
+
+fn draw_player(screen: &mut Screen, player: &Player, (col, row): (i32, i32), is_current: bool) {
+    let color = if is_current {
+        Color::Blue
+    } else {
+        Color::DarkBlue
+    };
+    screen.put(col, row, '●', color);
+
+    let (dx, dy, arrow) = match player.direction {
+        Direction::Up => (0, -1, '▲'),
+        Direction::Down => (0, 1, '▼'),
+        Direction::Left => (-1, 0, '◀'),
+        Direction::Right => (1, 0, '▶'),
+    };
+    screen.put(col + dx, row + dy, arrow, color);
+
+    let health_color = if player.health * 2 > player.max_health {
+        Color::DarkGreen
+    } else if player.health * 4 > player.max_health {
+        Color::DarkYellow
+    } else {
+        Color::Red
+    };
+    let label = &player.name;
+    screen.text(
+        col - label.len() as i32 / 2,
+        row - 1 + dy.min(0),
+        label,
+        health_color,
+    );
+}
+
+fn draw_offscreen_arrow(screen: &mut Screen, view: &View, dx: f32, dy: f32) {
+    // Horizontal distances are stretched by the column ratio like the arena.
+    let angle = dy.atan2(dx * view.columns);
+    let half_w = view.center_x - 1.0;
+    let half_h = view.center_y - 2.0;
+    let col = (view.center_x + half_w * angle.cos()).round() as i32;
+    let row = (view.center_y + half_h * angle.sin()).round() as i32;
+
+    let octant = ((angle / std::f32::consts::FRAC_PI_4).round() as i32).rem_euclid(8);
+    let arrow = ['→', '↘', '↓', '↙', '←', '↖', '↑', '↗'][octant as usize];
+    screen.put(col, row, arrow, Color::Red);
+}
+
+fn draw_hud(screen: &mut Screen, current: &Player) {
+    for col in 0..screen.width() as i32 {
+        screen.set(col, 0, ' ', Color::White, Color::Black);
+    }
+    let hud = format!(
+        "{} | Health {}/{} | Kills: {} | Deths: {} | Damage: {} | Fire rate: {:.2}",
+        current.name,
+        current.health,
+        current.max_health,
+        current.kills,
+        current.deths,
+        current.bullet_damage,
+        current.fire_rate,
+    );
+    screen.text(0, 0, &hud, Color::White);
+}
+
+/// Recent kills right-aligned below the HUD, newest at the bottom.
+pub fn draw_kill_feed(screen: &mut Screen, entries: &[KillFeedEntry], own_name: &str) {
+    for (i, entry) in entries.iter().enumerate() {
+        let text = format!(" {} killed {} ", entry.killer, entry.victim);
+        let col = screen.width() as i32 - text.chars().count() as i32;
+        let row = 1 + i as i32;
+        let fg = if entry.killer == own_name || entry.victim == own_name {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        for (j, ch) in text.chars().enumerate() {
+            screen.set(col + j as i32, row, ch, fg, Color::Black);
+        }
+    }
+}
+
+/// Damage numbers and pickups rising from where they happened.
+pub fn draw_floating_texts(screen: &mut Screen, texts: &[FloatingText], current: &Player) {
+    let view = View::new(screen, (current.x, current.y));
+    let arena_bottom = screen.height() as i32 - 1;
+
+    for text in texts {
+        let (col, row) = view.cell(text.x, text.y);
+        let row = row - 1 - (text.progress() * 2.0) as i32;
+        if row < 1 || row >= arena_bottom {
+            continue;
+        }
+        let color = match text.kind {
+            FloatingTextKind::DamageDealt => Color::Yellow,
+            FloatingTextKind::DamageTaken => Color::Red,
+            FloatingTextKind::Pickup => Color::Green,
+            FloatingTextKind::Respawn => Color::Cyan,
+        };
+        let col = col - text.text.chars().count() as i32 / 2;
+        screen.text(col, row, &text.text, color);
+    }
+}
+
+/// Recent chat above the help line, newest at the bottom, and the line being
+/// typed in place of the help line. Lines dim and then disappear unless the
+/// chat is open.
+pub fn draw_chat(screen: &mut Screen, entries: &[ChatEntry], typing: Option<&str>) {
+    let bottom = screen.height() as i32 - 1;
+    let width = screen.width() as i32;
+
+    for (i, entry) in entries.iter().rev().enumerate() {
+        let opacity = if typing.is_some() {
+            1.0
+        } else {
+            entry.opacity()
+        };
+        if opacity <= 0.0 {
+            continue;
+        }
+        let message = &entry.message;
+        let line = format!(
+            "[{}] {}: {}",
+            message.time_of_day(),
+            message.name,
+            message.text
+        );
+        let fg = if opacity < 1.0 {
+            Color::DarkGrey
+        } else if message.sender.is_none() {
+            Color::DarkYellow
+        } else {
+            Color::White
+        };
+        let row = bottom - 1 - i as i32;
+        for (col, ch) in line.chars().take(width as usize).enumerate() {
+            screen.set(col as i32, row, ch, fg, Color::Black);
+        }
+    }
+
+    if let Some(text) = typing {
+        for col in 0..width {
+            screen.set(col, bottom, ' ', Color::White, Color::Black);
+        }
+        let line = format!("Say: {}_", text);
+        // Keep the end of a long line in view.
+        let skip = line.chars().count().saturating_sub(width as usize);
+        let line: String = line.chars().skip(skip).collect();
+        screen.text(0, bottom, &line, Color::Yellow);
+    }
+}
+
+/// Ping and snapshot loss right-aligned in the HUD line, coloured by quality.
+pub fn draw_net_status(screen: &mut Screen, ping_ms: Option<u32>, packet_loss: Option<f32>) {
+    let ping = ping_ms.map_or("-".to_string(), |ping| format!("{}ms", ping));
+    let loss = packet_loss.map_or("-".to_string(), |loss| format!("{:.0}%", loss * 100.0));
+    let text = format!(" ping {} loss {} ", ping, loss);
+
+    let bad_ping = ping_ms.unwrap_or(0);
+    let bad_loss = packet_loss.unwrap_or(0.0);
+    let fg = if bad_ping > 200 || bad_loss > 0.10 {
+        Color::Red
+    } else if bad_ping > 100 || bad_loss > 0.02 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    let col = screen.width() as i32 - text.chars().count() as i32;
+    for (i, ch) in text.chars().enumerate() {
+        screen.set(col + i as i32, 0, ch, fg, Color::Black);
+    }
+}
+
+/// Debug panel below the HUD: frame rate, traffic, diff sizes, entity
+/// counts and the local player's server position, which is where it is
+/// drawn, next to a wall-blind extrapolation along its move target.
+pub fn draw_debug(
+    screen: &mut Screen,
+    state: &ClientState,
+    current: &Player,
+    viewport: (f32, f32),
+    fps: u32,
+) {
+    let graph = state.net_graph();
+    let since_diff = graph
+        .since_last_diff()
+        .map_or(0.0, |elapsed| elapsed.as_secs_f32());
+    let extrapolated = current.extrapolate(since_diff);
+    let (players, bullets, modifiers) = state.game_state.as_ref().map_or((0, 0, 0), |gs| {
+        (
+            gs.players.values().filter(|p| p.to_render).count(),
+            gs.bullets.len(),
+            gs.modifieres.len(),
+        )
+    });
+    let diff_sizes = graph.diff_sizes().map_or("-".to_string(), |sizes| {
+        format!("{}/{}/{} B", sizes.last, sizes.average, sizes.max)
+    });
+
+    let lines = [
+        format!(
+            "FPS {}  tick {}  seq {}",
+            fps, state.server_tick, state.last_seq
+        ),
+        format!(
+            "Snapshots {}/s  {:.1} KB/s",
+            graph.snapshot_rate(),
+            graph.bytes_per_second() as f32 / 1024.0
+        ),
+        format!("Diff last/avg/max {}", diff_sizes),
+        format!(
+            "Players {}/{}  bullets {}  mods {}",
+            players,
+            state.player_total(),
+            bullets,
+            modifiers
+        ),
+        format!("Server/drawn {:.2}, {:.2}", current.x, current.y),
+        format!(
+            "Extrapolated {:.2}, {:.2} (no walls)",
+            extrapolated.0, extrapolated.1
+        ),
+        format!(
+            "Relevance {:.0}x{:.0} tiles",
+            viewport.0 * 2.0,
+            viewport.1 * 2.0
+        ),
+    ];
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    for (i, line) in lines.iter().enumerate() {
+        let row = 1 + i as i32;
+        for col in 0..width as i32 {
+            screen.set(col, row, ' ', Color::White, Color::Black);
+        }
+        screen.text(1, row, line, Color::White);
+    }
+}
+
+/// Box in the middle of the arena while the server is silent.
+pub fn draw_interrupted(screen: &mut Screen, silence: Duration, attempts: u32) {
+    let detail = if attempts == 0 {
+        format!("No response from the server for {}s", silence.as_secs())
+    } else {
+        format!(
+            "No response for {}s, reconnecting ({}/{})",
+            silence.as_secs(),
+            attempts,
+            config::RECONNECT_ATTEMPTS
+        )
+    };
+    let lines = ["Connection interrupted", detail.as_str()];
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as i32
+        + 4;
+    let left = (screen.width() as i32 - width) / 2;
+    let top = screen.height() as i32 / 2 - 2;
+
+    for row in top..top + 4 {
+        for col in left..left + width {
+            screen.set(col, row, ' ', Color::White, Color::DarkRed);
+        }
+    }
+    for (i, line) in lines.iter().enumerate() {
+        screen.text(left + 2, top + 1 + i as i32, line, Color::White);
+    }
+}
+
+/// Scoreboard overlay in the middle of the screen, best score first.
+pub fn draw_scoreboard(screen: &mut Screen, entries: &[ScoreboardEntry], current_id: Option<u32>) {
+    let width = 62.min(screen.width() as i32);
+    let left = (screen.width() as i32 - width) / 2;
+    let top = 2;
+    let rows = entries
+        .len()
+        .min(screen.height().saturating_sub(6) as usize);
+
+    for row in top..top + rows as i32 + 3 {
+        for col in left..left + width {
+            screen.set(col, row, ' ', Color::White, Color::Black);
+        }
+    }
+
+    let header = format!(
+        "{:<3}{:<17}{:>6}{:>6}{:>6}{:>7}{:>4}{:>6}{:>6}",
+        "#", "Name", "Kills", "Deths", "K/D", "Ping", "Dmg", "Rate", "Speed"
+    );
+    screen.text(
+        left + 1,
+        top,
+        &format!("Scoreboard - {} players", entries.len()),
+        Color::White,
+    );
+    screen.text(left + 1, top + 1, &header, Color::DarkGrey);
+
+    for (i, entry) in entries.iter().take(rows).enumerate() {
+        let ping = entry
+            .ping_ms
+            .map_or("-".to_string(), |ping| format!("{}ms", ping));
+        let line = format!(
+            "{:<3}{:<17}{:>6}{:>6}{:>6.2}{:>7}{:>4}{:>6.2}{:>6}",
+            i + 1,
+            entry.name,
+            entry.kills,
+            entry.deths,
+            entry.kill_death_ratio(),
+            ping,
+            entry.bullet_damage,
+            entry.fire_rate,
+            entry.walk_speed,
+        );
+        let color = if Some(entry.id) == current_id {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        screen.text(left + 1, top + 2 + i as i32, &line, color);
+    }
+}
+
+pub fn draw_loading(screen: &mut Screen, frame: u32, (received, total): (usize, usize)) {
+    let loading = format!("Loading{}", ".".repeat((frame % 4) as usize));
+    screen.text(2, 1, &loading, Color::White);
+
+    if total > 0 {
+        let percent = received as f32 / total as f32 * 100.0;
+        let progress = format!("Downloading map: {}/{} ({:.1}%)", received, total, percent);
+        screen.text(2, 3, &progress, Color::White);
+
+        let bar_width = 30;
+        let filled = bar_width * received / total;
+        for i in 0..bar_width {
+            let bg = if i < filled {
+                Color::Green
+            } else {
+                Color::DarkGrey
+            };
+            screen.set(2 + i as i32, 4, ' ', Color::Reset, bg);
+        }
+    }
+}
+
+/// Final screen: the server shut down or the connection was lost.
+pub fn draw_closed(screen: &mut Screen, title: &str, reason: &str) {
+    screen.text(2, 1, title, Color::White);
+    screen.text(2, 3, reason, Color::Grey);
+    screen.text(2, 5, "Press Esc or Q to quit", Color::DarkGrey);
+}
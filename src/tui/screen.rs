@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor, queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, ClearType},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Cell {
+    const BLANK: Cell = Cell {
+        ch: ' ',
+        fg: Color::Reset,
+        bg: Color::Reset,
+    };
+}
+
+/// A frame of terminal cells. Only rows that changed since the last
+/// `flush` are written, which keeps the output small over SSH.
+pub struct Screen {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    shown: Vec<Cell>,
+}
+
+impl Screen {
+    pub fn new(width: u16, height: u16) -> Self {
+        let len = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            cells: vec![Cell::BLANK; len],
+            shown: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Starts a new frame; a size change forces a full redraw.
+    pub fn clear(&mut self, width: u16, height: u16) {
+        if (width, height) != (self.width, self.height) {
+            *self = Self::new(width, height);
+        } else {
+            self.cells.fill(Cell::BLANK);
+        }
+    }
+
+    /// Forgets what is on the terminal so the next `flush` redraws everything.
+    pub fn invalidate(&mut self) {
+        self.shown.clear();
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, ch: char, fg: Color, bg: Color) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let index = y as usize * self.width as usize + x as usize;
+        self.cells[index] = Cell { ch, fg, bg };
+    }
+
+    /// Draws `ch` over the existing background.
+    pub fn put(&mut self, x: i32, y: i32, ch: char, fg: Color) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let index = y as usize * self.width as usize + x as usize;
+        let bg = self.cells[index].bg;
+        self.cells[index] = Cell { ch, fg, bg };
+    }
+
+    pub fn text(&mut self, x: i32, y: i32, text: &str, fg: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(x + i as i32, y, ch, fg);
+        }
+    }
+
+    pub fn flush(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let full = self.shown.len() != self.cells.len();
+        if full {
+            queue!(out, terminal::Clear(ClearType::All))?;
+        }
+
+        let width = self.width as usize;
+        for y in 0..self.height as usize {
+            let row = &self.cells[y * width..(y + 1) * width];
+            if !full && row == &self.shown[y * width..(y + 1) * width] {
+                continue;
+            }
+
+            queue!(out, cursor::MoveTo(0, y as u16))?;
+            let mut colors = None;
+            for cell in row {
+                if colors != Some((cell.fg, cell.bg)) {
+                    queue!(
+                        out,
+                        SetForegroundColor(cell.fg),
+                        SetBackgroundColor(cell.bg)
+                    )?;
+                    colors = Some((cell.fg, cell.bg));
+                }
+                queue!(out, Print(cell.ch))?;
+            }
+        }
+
+        queue!(out, ResetColor)?;
+        out.flush()?;
+        self.shown.clone_from(&self.cells);
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    io::{self, Write},
+    panic,
+};
+
+use crossterm::{
+    cursor, event, execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+/// Raw mode, alternate screen and hidden cursor for as long as it lives.
+/// The terminal is restored on drop and before a panic message is printed.
+pub struct Terminal {
+    release_events: bool,
+}
+
+impl Terminal {
+    pub fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+
+        // Terminals without the kitty keyboard protocol never report key
+        // releases, so held keys have to be guessed from auto-repeat.
+        let release_events = terminal::supports_keyboard_enhancement().unwrap_or(false)
+            && execute!(
+                io::stdout(),
+                event::PushKeyboardEnhancementFlags(
+                    event::KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )
+            .is_ok();
+
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore(release_events);
+            default_hook(info);
+        }));
+
+        Ok(Self { release_events })
+    }
+
+    pub fn release_events(&self) -> bool {
+        self.release_events
+    }
+
+    /// Terminal size in columns and rows.
+    pub fn size(&self) -> (u16, u16) {
+        terminal::size().unwrap_or((80, 24))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        restore(self.release_events);
+    }
+}
+
+fn restore(release_events: bool) {
+    let mut stdout = io::stdout();
+    if release_events {
+        let _ = execute!(stdout, event::PopKeyboardEnhancementFlags);
+    }
+    let _ = execute!(stdout, cursor::Show, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+    let _ = stdout.flush();
+}
@@ -0,0 +1,134 @@
+use macroquad::prelude::*;
+
+use crate::client::bindings::{Action, Binding, Bindings};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Capture {
+    Replace,
+    Add,
+}
+
+/// In-game screen for changing key bindings. Its own controls are fixed so
+/// a bad binding can always be undone.
+#[derive(Default)]
+pub struct BindingsScreen {
+    selected: usize,
+    capture: Option<Capture>,
+    status: Option<String>,
+}
+
+impl BindingsScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles input for one frame; false once the screen is closed.
+    pub fn update(&mut self, bindings: &mut Bindings) -> bool {
+        let action = Action::ALL[self.selected];
+
+        if let Some(capture) = self.capture {
+            if is_key_pressed(KeyCode::Escape) {
+                self.capture = None;
+            } else if let Some(binding) = Binding::pressed() {
+                let mut keys = match capture {
+                    Capture::Replace => Vec::new(),
+                    Capture::Add => bindings.get(action).to_vec(),
+                };
+                if !keys.contains(&binding) {
+                    keys.push(binding);
+                }
+                bindings.set(action, keys);
+                self.capture = None;
+                self.status = Some("Unsaved changes".to_string());
+            }
+            return true;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return false;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = self
+                .selected
+                .checked_sub(1)
+                .unwrap_or(Action::ALL.len() - 1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % Action::ALL.len();
+        }
+        if is_key_pressed(KeyCode::Enter) {
+            self.capture = Some(Capture::Replace);
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            self.capture = Some(Capture::Add);
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            bindings.set(action, Vec::new());
+            self.status = Some("Unsaved changes".to_string());
+        }
+        if is_key_pressed(KeyCode::R) {
+            *bindings = Bindings::default();
+            self.status = Some("Defaults restored, not saved".to_string());
+        }
+        if is_key_pressed(KeyCode::S) {
+            let saved = bindings.save();
+            self.status = Some(match (saved, Bindings::path()) {
+                (true, Some(path)) => format!("Saved to {}", path.display()),
+                _ => "Could not save key bindings".to_string(),
+            });
+        }
+        true
+    }
+
+    pub fn draw(&self, bindings: &Bindings) {
+        draw_rectangle(
+            0.0,
+            0.0,
+            screen_width(),
+            screen_height(),
+            Color::new(0.0, 0.0, 0.0, 0.85),
+        );
+
+        draw_text("Key bindings", 20.0, 50.0, 30.0, WHITE);
+
+        let mut y = 90.0;
+        for (i, action) in Action::ALL.iter().enumerate() {
+            let keys = bindings
+                .get(*action)
+                .iter()
+                .map(Binding::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let keys = if i == self.selected && self.capture.is_some() {
+                "press a key or mouse button...".to_string()
+            } else if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                keys
+            };
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            draw_text(action.label(), 20.0, y, 25.0, color);
+            draw_text(&keys, 220.0, y, 25.0, color);
+            y += 30.0;
+        }
+
+        y += 20.0;
+        draw_text(
+            "Up/Down select | Enter rebind | Tab add key | Backspace clear",
+            20.0,
+            y,
+            20.0,
+            GRAY,
+        );
+        draw_text(
+            "S save | R defaults | Esc close",
+            20.0,
+            y + 25.0,
+            20.0,
+            GRAY,
+        );
+        if let Some(status) = &self.status {
+            draw_text(status, 20.0, y + 60.0, 20.0, LIGHTGRAY);
+        }
+    }
+}
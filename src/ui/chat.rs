@@ -0,0 +1,122 @@
+use macroquad::prelude::*;
+
+use crate::{client::state::ChatEntry, config};
+
+const FONT_SIZE: f32 = 20.0;
+const ROW_HEIGHT: f32 = 22.0;
+const BOX_HEIGHT: f32 = 28.0;
+const BOX_WIDTH: f32 = 520.0;
+
+pub enum ChatAction {
+    Send(String),
+    Close,
+}
+
+/// Line being typed. While it is open the game ignores key bindings.
+#[derive(Default)]
+pub struct ChatBox {
+    text: String,
+}
+
+impl ChatBox {
+    pub fn new() -> Self {
+        // Drop whatever was typed while playing, including the key that
+        // opened the box.
+        while get_char_pressed().is_some() {}
+        Self::default()
+    }
+
+    /// Handles one frame of typing. Enter sends, Esc closes without sending.
+    pub fn update(&mut self) -> Option<ChatAction> {
+        let typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(ChatAction::Close);
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            let text = self.text.trim().to_string();
+            return Some(if text.is_empty() {
+                ChatAction::Close
+            } else {
+                ChatAction::Send(text)
+            });
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.text.pop();
+        }
+        for c in typed {
+            if !c.is_control() && self.text.chars().count() < config::MAX_CHAT_LEN {
+                self.text.push(c);
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self) {
+        let top = screen_height() - BOX_HEIGHT - 10.0;
+        draw_rectangle(
+            10.0,
+            top,
+            BOX_WIDTH,
+            BOX_HEIGHT,
+            Color::new(0.0, 0.0, 0.0, 0.7),
+        );
+        draw_rectangle_lines(10.0, top, BOX_WIDTH, BOX_HEIGHT, 2.0, YELLOW);
+        let cursor = if (get_time() * 2.0) as i64 % 2 == 0 {
+            "_"
+        } else {
+            ""
+        };
+        draw_text(
+            &format!("Say: {}{}", self.text, cursor),
+            18.0,
+            top + 20.0,
+            FONT_SIZE,
+            WHITE,
+        );
+    }
+}
+
+/// Recent chat above the chat box, newest at the bottom. Lines fade out
+/// unless the box is open.
+pub fn draw_chat_log(entries: &[ChatEntry], open: bool) {
+    let bottom = screen_height() - BOX_HEIGHT - 20.0;
+    for (i, entry) in entries.iter().rev().enumerate() {
+        let opacity = if open { 1.0 } else { entry.opacity() };
+        if opacity <= 0.0 {
+            continue;
+        }
+        let message = &entry.message;
+        let y = bottom - ROW_HEIGHT * i as f32;
+        let name_color = if message.sender.is_some() {
+            SKYBLUE
+        } else {
+            ORANGE
+        };
+        let prefix = format!("[{}] {}: ", message.time_of_day(), message.name);
+        let prefix_width = measure_text(&prefix, None, FONT_SIZE as u16, 1.0).width;
+        let text_width = measure_text(&message.text, None, FONT_SIZE as u16, 1.0).width;
+
+        draw_rectangle(
+            10.0,
+            y - FONT_SIZE * 0.75,
+            prefix_width + text_width + 12.0,
+            ROW_HEIGHT - 2.0,
+            Color::new(0.0, 0.0, 0.0, 0.5 * opacity),
+        );
+        draw_text(
+            &prefix,
+            16.0,
+            y,
+            FONT_SIZE,
+            Color::new(name_color.r, name_color.g, name_color.b, opacity),
+        );
+        draw_text(
+            &message.text,
+            16.0 + prefix_width,
+            y,
+            FONT_SIZE,
+            Color::new(1.0, 1.0, 1.0, opacity),
+        );
+    }
+}
@@ -0,0 +1,11 @@
+use macroquad::prelude::*;
+
+/// Final screen once the game is over: the server shut down (`title` is
+/// "Server closed") or the connection was lost ("Disconnected").
+pub fn draw_closed_screen(title: &str, reason: &str) {
+    clear_background(BLACK);
+
+    draw_text(title, 20.0, 50.0, 30.0, WHITE);
+    draw_text(reason, 20.0, 90.0, 25.0, GRAY);
+    draw_text("Press Esc or Q to quit", 20.0, 130.0, 20.0, GRAY);
+}
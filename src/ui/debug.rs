@@ -0,0 +1,199 @@
+use macroquad::prelude::*;
+
+use crate::{
+    client::state::ClientState,
+    config,
+    game::{player::Player, state::GameState},
+};
+
+const FONT_SIZE: f32 = 18.0;
+const LINE_HEIGHT: f32 = 20.0;
+const PANEL_WIDTH: f32 = 380.0;
+const GRAPH_BUCKETS: usize = 60;
+const GRAPH_HEIGHT: f32 = 60.0;
+
+/// F3 cycles through these.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DebugMode {
+    #[default]
+    Off,
+    /// Numbers and the net graph.
+    Stats,
+    /// Also hit radii, move targets and the relevance viewport in the world.
+    World,
+}
+
+impl DebugMode {
+    pub fn next(self) -> Self {
+        match self {
+            DebugMode::Off => DebugMode::Stats,
+            DebugMode::Stats => DebugMode::World,
+            DebugMode::World => DebugMode::Off,
+        }
+    }
+}
+
+/// Panel below the HUD: frame rate, traffic, diff sizes, entity counts and
+/// the local player's server position, which is where it is drawn, next to
+/// a wall-blind extrapolation along its move target.
+pub fn draw_debug_panel(state: &ClientState, player: &Player, viewport: (f32, f32)) {
+    let graph = state.net_graph();
+    let since_diff = graph
+        .since_last_diff()
+        .map_or(0.0, |elapsed| elapsed.as_secs_f32());
+    let extrapolated = player.extrapolate(since_diff);
+    let drift = ((extrapolated.0 - player.x).powi(2) + (extrapolated.1 - player.y).powi(2)).sqrt();
+    let (players, bullets, modifiers) = state.game_state.as_ref().map_or((0, 0, 0), |gs| {
+        (
+            gs.players.values().filter(|p| p.to_render).count(),
+            gs.bullets.len(),
+            gs.modifieres.len(),
+        )
+    });
+    let diff_sizes = graph.diff_sizes().map_or("-".to_string(), |sizes| {
+        format!("{} / {} / {} B", sizes.last, sizes.average, sizes.max)
+    });
+
+    let lines = [
+        format!("FPS {}", get_fps()),
+        format!(
+            "Server tick {}  diff seq {}",
+            state.server_tick, state.last_seq
+        ),
+        format!(
+            "Snapshots {}/s  {:.1} KB/s",
+            graph.snapshot_rate(),
+            graph.bytes_per_second() as f32 / 1024.0
+        ),
+        format!("Diff size last/avg/max {}", diff_sizes),
+        format!(
+            "Visible players {} of {}  bullets {}  modifiers {}",
+            players,
+            state.player_total(),
+            bullets,
+            modifiers
+        ),
+        format!("Server/drawn {:.2}, {:.2}", player.x, player.y),
+        format!(
+            "Extrapolated {:.2}, {:.2}  (+{:.2} in {:.0}ms, no walls)",
+            extrapolated.0,
+            extrapolated.1,
+            drift,
+            since_diff * 1000.0
+        ),
+        format!(
+            "Relevance {:.0} x {:.0} tiles",
+            viewport.0 * 2.0,
+            viewport.1 * 2.0
+        ),
+    ];
+
+    let left = 10.0;
+    let top = 60.0;
+    let height = LINE_HEIGHT * lines.len() as f32 + GRAPH_HEIGHT + 30.0;
+    draw_rectangle(
+        left - 5.0,
+        top - 5.0,
+        PANEL_WIDTH,
+        height,
+        Color::new(0.0, 0.0, 0.0, 0.7),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(
+            line,
+            left,
+            top + 12.0 + LINE_HEIGHT * i as f32,
+            FONT_SIZE,
+            WHITE,
+        );
+    }
+
+    let graph_top = top + LINE_HEIGHT * lines.len() as f32 + 10.0;
+    draw_net_graph(state, left, graph_top, PANEL_WIDTH - 10.0);
+}
+
+/// Bytes received over `NET_GRAPH_WINDOW`: diffs in green, the rest in blue.
+fn draw_net_graph(state: &ClientState, left: f32, top: f32, width: f32) {
+    let buckets = state.net_graph().buckets(GRAPH_BUCKETS);
+    let peak = buckets
+        .iter()
+        .map(|(diffs, other)| diffs + other)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let bar_width = width / GRAPH_BUCKETS as f32;
+    let bottom = top + GRAPH_HEIGHT;
+
+    draw_line(left, bottom, left + width, bottom, 1.0, GRAY);
+    for (i, (diffs, other)) in buckets.into_iter().enumerate() {
+        let x = left + bar_width * i as f32;
+        let diff_height = GRAPH_HEIGHT * diffs as f32 / peak as f32;
+        let other_height = GRAPH_HEIGHT * other as f32 / peak as f32;
+        draw_rectangle(x, bottom - diff_height, bar_width - 1.0, diff_height, GREEN);
+        draw_rectangle(
+            x,
+            bottom - diff_height - other_height,
+            bar_width - 1.0,
+            other_height,
+            SKYBLUE,
+        );
+    }
+    draw_text(
+        &format!(
+            "peak {} B / {}ms",
+            peak,
+            config::NET_GRAPH_WINDOW.as_millis() / GRAPH_BUCKETS as u128
+        ),
+        left,
+        bottom + 16.0,
+        16.0,
+        GRAY,
+    );
+}
+
+/// Hit radii, move targets and the server relevance rectangle, in the same
+/// screen space as `GameState::render`.
+pub fn draw_debug_world(game_state: &GameState, player_pos: (f32, f32), viewport: (f32, f32)) {
+    let to_screen = |x: f32, y: f32| {
+        (
+            screen_width() / 2.0 + (x - player_pos.0) * config::TILE_SIZE,
+            screen_height() / 2.0 + (y - player_pos.1) * config::TILE_SIZE,
+        )
+    };
+
+    for player in game_state.players.values().filter(|p| p.to_render) {
+        let (x, y) = to_screen(player.x, player.y);
+        draw_circle_lines(x, y, player.radius * config::TILE_SIZE, 1.0, RED);
+        if let Some((tx, ty)) = player.move_target {
+            let (tx, ty) = to_screen(tx, ty);
+            draw_line(x, y, tx, ty, 1.0, YELLOW);
+            draw_line(tx - 4.0, ty - 4.0, tx + 4.0, ty + 4.0, 1.0, YELLOW);
+            draw_line(tx - 4.0, ty + 4.0, tx + 4.0, ty - 4.0, 1.0, YELLOW);
+        }
+    }
+    for bullet in game_state.bullets.values() {
+        let (x, y) = to_screen(bullet.x, bullet.y);
+        draw_circle_lines(x, y, bullet.hit_radius * config::TILE_SIZE, 1.0, ORANGE);
+    }
+    for modifier in game_state.modifieres.values() {
+        let (x, y) = to_screen(modifier.x, modifier.y);
+        draw_circle_lines(
+            x,
+            y,
+            config::MODIFIER_PICKUP_RADIUS * config::TILE_SIZE,
+            1.0,
+            LIME,
+        );
+    }
+
+    let (half_w, half_h) = viewport;
+    let (left, top) = to_screen(player_pos.0 - half_w, player_pos.1 - half_h);
+    draw_rectangle_lines(
+        left,
+        top,
+        half_w * 2.0 * config::TILE_SIZE,
+        half_h * 2.0 * config::TILE_SIZE,
+        2.0,
+        MAGENTA,
+    );
+}
@@ -0,0 +1,65 @@
+use macroquad::prelude::*;
+
+use crate::{
+    client::state::{FloatingText, FloatingTextKind, KillFeedEntry},
+    config,
+};
+
+const FEED_FONT_SIZE: f32 = 20.0;
+const FEED_ROW_HEIGHT: f32 = 22.0;
+const FLOATING_FONT_SIZE: f32 = 20.0;
+/// How far a floating text rises before it disappears, in tiles.
+const FLOATING_RISE: f32 = 1.5;
+
+/// Recent kills in the top right corner, newest at the bottom. Rows with the
+/// local player are highlighted.
+pub fn draw_kill_feed(entries: &[KillFeedEntry], own_name: Option<&str>) {
+    for (i, entry) in entries.iter().enumerate() {
+        let text = format!("{} killed {}", entry.killer, entry.victim);
+        let dimensions = measure_text(&text, None, FEED_FONT_SIZE as u16, 1.0);
+        let x = screen_width() - dimensions.width - 10.0;
+        let y = 20.0 + FEED_ROW_HEIGHT * i as f32;
+
+        let own = own_name.is_some_and(|name| name == entry.killer || name == entry.victim);
+        draw_rectangle(
+            x - 4.0,
+            y - FEED_FONT_SIZE * 0.75,
+            dimensions.width + 8.0,
+            FEED_ROW_HEIGHT - 2.0,
+            Color::new(0.0, 0.0, 0.0, 0.5),
+        );
+        draw_text(
+            &text,
+            x,
+            y,
+            FEED_FONT_SIZE,
+            if own { YELLOW } else { WHITE },
+        );
+    }
+}
+
+/// Damage numbers and pickups rising from where they happened.
+pub fn draw_floating_texts(texts: &[FloatingText], player_pos: (f32, f32)) {
+    for text in texts {
+        let progress = text.progress();
+        let x = screen_width() / 2.0 + (text.x - player_pos.0) * config::TILE_SIZE;
+        let y = screen_height() / 2.0
+            + (text.y - player_pos.1 - FLOATING_RISE * progress) * config::TILE_SIZE
+            - config::TILE_SIZE;
+
+        let color = match text.kind {
+            FloatingTextKind::DamageDealt => YELLOW,
+            FloatingTextKind::DamageTaken => RED,
+            FloatingTextKind::Pickup => GREEN,
+            FloatingTextKind::Respawn => SKYBLUE,
+        };
+        let dimensions = measure_text(&text.text, None, FLOATING_FONT_SIZE as u16, 1.0);
+        draw_text(
+            &text.text,
+            x - dimensions.width / 2.0,
+            y,
+            FLOATING_FONT_SIZE,
+            Color::new(color.r, color.g, color.b, 1.0 - progress),
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use macroquad::prelude::*;
+
+use crate::{
+    client::profile::{Profile, RecentServer},
+    config,
+};
+
+const FIELD_X: f32 = 200.0;
+const FIELD_WIDTH: f32 = 320.0;
+const ROW_HEIGHT: f32 = 40.0;
+const MAX_ADDRESS_LEN: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Address,
+    TcpPort,
+    Nickname,
+    Connect,
+    Recent(usize),
+}
+
+pub enum MenuAction {
+    Connect {
+        address: String,
+        tcp_port: u16,
+        nickname: String,
+    },
+    Quit,
+}
+
+/// Startup menu: server address, TCP port and nickname fields, a Connect
+/// button and the recent servers from the profile.
+pub struct Menu {
+    address: String,
+    tcp_port: String,
+    nickname: String,
+    recent: Vec<RecentServer>,
+    focus: Focus,
+    error: Option<String>,
+    connecting: Option<String>,
+}
+
+impl Menu {
+    pub fn new(profile: &Profile) -> Self {
+        let (address, tcp_port) = match profile.recent_servers.first() {
+            Some(server) => (server.address.clone(), server.tcp_port),
+            None => ("127.0.0.1".to_string(), config::TCP_PORT as u16),
+        };
+        Self {
+            address,
+            tcp_port: tcp_port.to_string(),
+            nickname: profile.nickname.clone(),
+            recent: profile.recent_servers.clone(),
+            focus: Focus::Address,
+            error: None,
+            connecting: None,
+        }
+    }
+
+    pub fn set_server(&mut self, address: &str, tcp_port: u16) {
+        self.address = address.to_string();
+        self.tcp_port = tcp_port.to_string();
+    }
+
+    pub fn set_recent(&mut self, recent: &[RecentServer]) {
+        self.recent = recent.to_vec();
+        if let Focus::Recent(i) = self.focus
+            && i >= self.recent.len()
+        {
+            self.focus = Focus::Address;
+        }
+    }
+
+    /// Shown under the fields until the next connect attempt.
+    pub fn set_error(&mut self, error: impl Into<String>) {
+        self.error = Some(error.into());
+        self.connecting = None;
+    }
+
+    pub fn set_connecting(&mut self, address: Option<String>) {
+        self.connecting = address;
+        if self.connecting.is_some() {
+            self.error = None;
+        }
+    }
+
+    fn focus_order(&self) -> Vec<Focus> {
+        let mut order = vec![
+            Focus::Address,
+            Focus::TcpPort,
+            Focus::Nickname,
+            Focus::Connect,
+        ];
+        order.extend((0..self.recent.len()).map(Focus::Recent));
+        order
+    }
+
+    fn move_focus(&mut self, step: isize) {
+        let order = self.focus_order();
+        let current = order.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(order.len() as isize);
+        self.focus = order[next as usize];
+    }
+
+    fn connect_action(&mut self) -> Option<MenuAction> {
+        let Ok(tcp_port) = self.tcp_port.trim().parse() else {
+            self.set_error(format!("Invalid TCP port: {}", self.tcp_port));
+            return None;
+        };
+        Some(MenuAction::Connect {
+            address: self.address.trim().to_string(),
+            tcp_port,
+            nickname: self.nickname.trim().to_string(),
+        })
+    }
+
+    fn pick_recent(&mut self, i: usize) -> Option<MenuAction> {
+        let server = self.recent.get(i)?.clone();
+        self.set_server(&server.address, server.tcp_port);
+        self.connect_action()
+    }
+
+    /// Handles one frame of input. Input is ignored while connecting.
+    pub fn update(&mut self) -> Option<MenuAction> {
+        let typed: Vec<char> = std::iter::from_fn(get_char_pressed).collect();
+        if self.connecting.is_some() {
+            return None;
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(MenuAction::Quit);
+        }
+        if is_key_pressed(KeyCode::Tab) {
+            let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+            self.move_focus(if shift { -1 } else { 1 });
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.move_focus(1);
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.move_focus(-1);
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (x, y) = mouse_position();
+            if let Some(focus) = self.focus_at(x, y) {
+                self.focus = focus;
+                match focus {
+                    Focus::Connect => return self.connect_action(),
+                    Focus::Recent(i) => return self.pick_recent(i),
+                    _ => {}
+                }
+            }
+        }
+
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) {
+            return match self.focus {
+                Focus::Recent(i) => self.pick_recent(i),
+                _ => self.connect_action(),
+            };
+        }
+
+        let (field, max_len) = match self.focus {
+            Focus::Address => (&mut self.address, MAX_ADDRESS_LEN),
+            Focus::TcpPort => (&mut self.tcp_port, 5),
+            Focus::Nickname => (&mut self.nickname, config::MAX_NICKNAME_LEN),
+            Focus::Connect | Focus::Recent(_) => return None,
+        };
+        if is_key_pressed(KeyCode::Backspace) {
+            field.pop();
+        }
+        for c in typed {
+            if !c.is_control() && field.chars().count() < max_len {
+                field.push(c);
+            }
+        }
+        None
+    }
+
+    fn field_rect(row: usize) -> Rect {
+        Rect::new(FIELD_X, 80.0 + row as f32 * ROW_HEIGHT, FIELD_WIDTH, 30.0)
+    }
+
+    fn connect_rect() -> Rect {
+        Rect::new(FIELD_X, 80.0 + 3.0 * ROW_HEIGHT, 120.0, 30.0)
+    }
+
+    fn recent_rect(i: usize) -> Rect {
+        Rect::new(20.0, 310.0 + i as f32 * 30.0, FIELD_X + FIELD_WIDTH, 26.0)
+    }
+
+    fn focus_at(&self, x: f32, y: f32) -> Option<Focus> {
+        let point = vec2(x, y);
+        [Focus::Address, Focus::TcpPort, Focus::Nickname]
+            .into_iter()
+            .enumerate()
+            .find(|(row, _)| Self::field_rect(*row).contains(point))
+            .map(|(_, focus)| focus)
+            .or_else(|| {
+                Self::connect_rect()
+                    .contains(point)
+                    .then_some(Focus::Connect)
+            })
+            .or_else(|| {
+                (0..self.recent.len())
+                    .find(|i| Self::recent_rect(*i).contains(point))
+                    .map(Focus::Recent)
+            })
+    }
+
+    pub fn draw(&self) {
+        clear_background(BLACK);
+        draw_text("termarena", 20.0, 50.0, 40.0, WHITE);
+
+        let fields = [
+            ("Server address", &self.address, Focus::Address),
+            ("TCP port", &self.tcp_port, Focus::TcpPort),
+            ("Nickname", &self.nickname, Focus::Nickname),
+        ];
+        for (row, (label, value, focus)) in fields.into_iter().enumerate() {
+            let rect = Self::field_rect(row);
+            let focused = self.focus == focus;
+            draw_text(label, 20.0, rect.y + 21.0, 22.0, GRAY);
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, DARKGRAY);
+            draw_rectangle_lines(
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h,
+                2.0,
+                if focused { YELLOW } else { GRAY },
+            );
+            let cursor = if focused && (get_time() * 2.0) as i64 % 2 == 0 {
+                "_"
+            } else {
+                ""
+            };
+            draw_text(
+                &format!("{}{}", value, cursor),
+                rect.x + 8.0,
+                rect.y + 21.0,
+                22.0,
+                WHITE,
+            );
+        }
+
+        let button = Self::connect_rect();
+        let button_color = if self.focus == Focus::Connect {
+            YELLOW
+        } else {
+            GRAY
+        };
+        draw_rectangle(button.x, button.y, button.w, button.h, DARKGREEN);
+        draw_rectangle_lines(button.x, button.y, button.w, button.h, 2.0, button_color);
+        draw_text("Connect", button.x + 22.0, button.y + 21.0, 22.0, WHITE);
+
+        if let Some(address) = &self.connecting {
+            draw_text(
+                &format!("Connecting to {}...", address),
+                button.x + button.w + 20.0,
+                button.y + 21.0,
+                20.0,
+                LIGHTGRAY,
+            );
+        }
+        if let Some(error) = &self.error {
+            draw_text(error, 20.0, 270.0, 20.0, RED);
+        }
+
+        if !self.recent.is_empty() {
+            draw_text("Recent servers", 20.0, 300.0, 22.0, GRAY);
+        }
+        for (i, server) in self.recent.iter().enumerate() {
+            let rect = Self::recent_rect(i);
+            let color = if self.focus == Focus::Recent(i) {
+                YELLOW
+            } else {
+                WHITE
+            };
+            draw_text(
+                &format!("{} (tcp {})", server.address, server.tcp_port),
+                rect.x + 8.0,
+                rect.y + 19.0,
+                20.0,
+                color,
+            );
+        }
+
+        draw_text(
+            "Tab/Up/Down move | Enter connect | Esc quit",
+            20.0,
+            screen_height() - 20.0,
+            20.0,
+            GRAY,
+        );
+    }
+}
@@ -1 +1,9 @@
+pub mod bindings;
+pub mod chat;
+pub mod closed;
+pub mod debug;
+pub mod feed;
 pub mod loading;
+pub mod menu;
+pub mod scoreboard;
+pub mod status;
@@ -0,0 +1,70 @@
+use macroquad::prelude::*;
+
+use crate::network::state::ScoreboardEntry;
+
+const COLUMNS: [(&str, f32); 9] = [
+    ("#", 0.0),
+    ("Name", 40.0),
+    ("Kills", 230.0),
+    ("Deths", 300.0),
+    ("K/D", 370.0),
+    ("Ping", 440.0),
+    ("Damage", 510.0),
+    ("Fire rate", 590.0),
+    ("Speed", 690.0),
+];
+const WIDTH: f32 = 760.0;
+const ROW_HEIGHT: f32 = 24.0;
+
+/// Full scoreboard shown while the scoreboard key is held, best score first.
+pub fn draw_scoreboard(entries: &[ScoreboardEntry], current_id: Option<u32>) {
+    let height = 70.0 + ROW_HEIGHT * entries.len().max(1) as f32;
+    let left = ((screen_width() - WIDTH) / 2.0).max(0.0);
+    let top = 60.0;
+
+    draw_rectangle(
+        left - 10.0,
+        top - 10.0,
+        WIDTH + 20.0,
+        height,
+        Color::new(0.0, 0.0, 0.0, 0.8),
+    );
+    draw_text(
+        &format!("Scoreboard - {} players", entries.len()),
+        left,
+        top + 15.0,
+        24.0,
+        WHITE,
+    );
+
+    let header_y = top + 45.0;
+    for (title, x) in COLUMNS {
+        draw_text(title, left + x, header_y, 20.0, GRAY);
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        let y = header_y + ROW_HEIGHT * (i + 1) as f32;
+        let color = if Some(entry.id) == current_id {
+            YELLOW
+        } else {
+            WHITE
+        };
+        let ping = entry
+            .ping_ms
+            .map_or("-".to_string(), |ping| format!("{}ms", ping));
+        let cells = [
+            (i + 1).to_string(),
+            entry.name.clone(),
+            entry.kills.to_string(),
+            entry.deths.to_string(),
+            format!("{:.2}", entry.kill_death_ratio()),
+            ping,
+            entry.bullet_damage.to_string(),
+            format!("{:.2}", entry.fire_rate),
+            entry.walk_speed.to_string(),
+        ];
+        for (text, (_, x)) in cells.iter().zip(COLUMNS) {
+            draw_text(text, left + x, y, 20.0, color);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use macroquad::prelude::*;
+
+use crate::config;
+
+/// Dims the game and explains that the server went quiet.
+pub fn draw_interrupted(silence: Duration, attempts: u32) {
+    draw_rectangle(
+        0.0,
+        0.0,
+        screen_width(),
+        screen_height(),
+        Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+
+    let title = "Connection interrupted";
+    let detail = if attempts == 0 {
+        format!("No response from the server for {}s", silence.as_secs())
+    } else {
+        format!(
+            "No response for {}s, reconnecting (attempt {}/{})",
+            silence.as_secs(),
+            attempts,
+            config::RECONNECT_ATTEMPTS
+        )
+    };
+    for (text, size, dy, color) in [(title, 32.0, 0.0, ORANGE), (&detail, 22.0, 34.0, LIGHTGRAY)] {
+        let width = measure_text(text, None, size as u16, 1.0).width;
+        draw_text(
+            text,
+            (screen_width() - width) / 2.0,
+            screen_height() / 2.0 + dy,
+            size,
+            color,
+        );
+    }
+}
+
+/// Ping and snapshot loss in the bottom right corner, coloured by quality.
+pub fn draw_net_indicator(ping_ms: Option<u32>, packet_loss: Option<f32>) {
+    let ping = ping_ms.map_or("-".to_string(), |ping| format!("{}ms", ping));
+    let loss = packet_loss.map_or("-".to_string(), |loss| format!("{:.0}%", loss * 100.0));
+    let text = format!("ping {}  loss {}", ping, loss);
+
+    let bad_ping = ping_ms.unwrap_or(0);
+    let bad_loss = packet_loss.unwrap_or(0.0);
+    let color = if bad_ping > 200 || bad_loss > 0.10 {
+        RED
+    } else if bad_ping > 100 || bad_loss > 0.02 {
+        YELLOW
+    } else {
+        GREEN
+    };
+
+    let width = measure_text(&text, None, 18, 1.0).width;
+    draw_text(
+        &text,
+        screen_width() - width - 10.0,
+        screen_height() - 10.0,
+        18.0,
+        color,
+    );
+}
@@ -1,7 +1,101 @@
-use std::net::UdpSocket;
+use std::io;
+use std::net::{IpAddr, SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
 
-pub fn get_local_ip() -> Option<String> {
-    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
-    socket.connect("8.8.8.8:80").ok()?;
-    socket.local_addr().ok().map(|addr| addr.ip().to_string())
+use socket2::{Domain, Protocol, Socket, Type};
+
+pub fn local_addresses() -> Vec<IpAddr> {
+    let interfaces = match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces,
+        Err(e) => {
+            eprintln!("Failed to list network interfaces: {:?}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut addresses: Vec<IpAddr> = interfaces
+        .iter()
+        .map(|interface| interface.ip())
+        .filter(|ip| !ip.is_loopback() && !is_link_local(ip))
+        .collect();
+    if addresses.is_empty() {
+        addresses = interfaces.iter().map(|interface| interface.ip()).collect();
+    }
+    addresses.sort();
+    addresses.dedup();
+
+    addresses
+}
+
+fn is_link_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_link_local(),
+        IpAddr::V6(ip) => (ip.segments()[0] & 0xffc0) == 0xfe80,
+    }
+}
+
+pub fn resolve_addr(addr: &str, default_port: u16) -> Result<SocketAddr, String> {
+    let addr = addr.trim();
+    if let Ok(socket_addr) = addr.parse::<SocketAddr>() {
+        return Ok(socket_addr);
+    }
+    if let Ok(ip) = addr.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, default_port));
+    }
+
+    let resolved = match addr.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse::<u16>() {
+            Ok(port) => (host, port).to_socket_addrs(),
+            Err(_) => return Err(format!("Invalid port in address: {}", addr)),
+        },
+        _ => (addr, default_port).to_socket_addrs(),
+    };
+
+    match resolved {
+        Ok(mut addrs) => addrs
+            .next()
+            .ok_or_else(|| format!("No addresses found for {}", addr)),
+        Err(e) => Err(format!("Failed to resolve {}: {}", addr, e)),
+    }
+}
+
+pub fn bind_udp(addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(!addr.ip().is_unspecified())?;
+    }
+    socket.bind(&addr.into())?;
+
+    Ok(socket.into())
+}
+
+/// Binds a UDP socket that other processes can bind as well, so several
+/// servers on one host can all hear broadcast discovery probes.
+pub fn bind_udp_shared(addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true)?;
+    socket.bind(&addr.into())?;
+
+    Ok(socket.into())
+}
+
+pub fn bind_tcp(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(!addr.ip().is_unspecified())?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(128)?;
+
+    Ok(socket.into())
+}
+
+pub fn bind_udp_for(target: SocketAddr) -> io::Result<UdpSocket> {
+    if target.is_ipv6() {
+        UdpSocket::bind("[::]:0")
+    } else {
+        UdpSocket::bind("0.0.0.0:0")
+    }
 }
@@ -0,0 +1,114 @@
+#![cfg(feature = "server")]
+
+use std::{
+    net::Ipv4Addr,
+    thread,
+    time::{Duration, Instant},
+};
+
+use termarena::{
+    client::{connection::Connection, tcp},
+    config,
+    network::state::AdminCommand,
+    server::{ServerBuilder, ServerEvent, ServerHandle},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn start_local(port: u16, tcp_port: u16) -> ServerHandle {
+    ServerBuilder::new()
+        .bind(Ipv4Addr::LOCALHOST.into())
+        .port(port)
+        .tcp_port(tcp_port)
+        .discovery(false)
+        .start()
+        .expect("server should start")
+}
+
+/// Joins over TCP and waits until the UDP side has its game state.
+fn join(server: &ServerHandle, name: &str) -> (Connection, u32) {
+    let connection = Connection::connect(server.local_addr(), server.tcp_addr(), name)
+        .expect("client should connect");
+    let id = connection
+        .state()
+        .id
+        .expect("TCP join should assign a player");
+    assert_eq!(
+        server.events().recv_timeout(TIMEOUT),
+        Ok(ServerEvent::PlayerJoined { id })
+    );
+
+    let deadline = Instant::now() + TIMEOUT;
+    while connection.state().game_state.is_none() {
+        assert!(Instant::now() < deadline, "no game state after binding");
+        thread::sleep(Duration::from_millis(10));
+    }
+    (connection, id)
+}
+
+#[test]
+fn embedded_server_reports_join_and_leave() {
+    let server = start_local(0, 0);
+    assert_ne!(server.local_addr().port(), 0);
+    assert_ne!(server.tcp_addr().port(), 0);
+
+    let (connection, id) = join(&server, "embedded");
+
+    connection.disconnect();
+    assert_eq!(
+        server.events().recv_timeout(TIMEOUT),
+        Ok(ServerEvent::PlayerLeft { id })
+    );
+
+    server.stop("test finished");
+}
+
+#[test]
+fn kicked_player_is_reported() {
+    let server = start_local(0, 0);
+    let (_connection, id) = join(&server, "kicked");
+
+    let response = tcp::admin(server.tcp_addr(), AdminCommand::Kick(id))
+        .expect("admin command should be answered");
+    assert!(response.starts_with("Kicked player"), "{}", response);
+    assert_eq!(
+        server.events().recv_timeout(TIMEOUT),
+        Ok(ServerEvent::PlayerLeft { id })
+    );
+}
+
+#[test]
+fn dropped_handle_frees_its_ports() {
+    let server = start_local(0, 0);
+    let (port, tcp_port) = (server.local_addr().port(), server.tcp_addr().port());
+    drop(server);
+
+    let server = start_local(port, tcp_port);
+    assert_eq!(server.local_addr().port(), port);
+    assert_eq!(server.tcp_addr().port(), tcp_port);
+}
+
+#[test]
+fn chat_over_the_rate_limit_is_dropped() {
+    let server = start_local(0, 0);
+    let (connection, id) = join(&server, "chatty");
+
+    for i in 0..=config::CHAT_RATE_LIMIT {
+        connection.send_chat(&format!("message {}", i));
+    }
+    for i in 0..config::CHAT_RATE_LIMIT {
+        assert_eq!(
+            server.events().recv_timeout(TIMEOUT),
+            Ok(ServerEvent::ChatMessage {
+                id,
+                text: format!("message {}", i)
+            })
+        );
+    }
+    assert!(
+        server
+            .events()
+            .recv_timeout(Duration::from_millis(300))
+            .is_err()
+    );
+}